@@ -0,0 +1,24 @@
+use template_nest::{
+    filling, filling_list, filling_text, Filling, TemplateNest, TemplateNestError,
+};
+
+fn main() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+
+    let mut simple_page: Filling = filling!(
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+    );
+    let mut components = filling_list![filling!(
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable in Simple Component"
+    )];
+    components.push(filling!(
+        "TEMPLATE": "01-simple-component",
+        "variable": filling_text!("Another Simple Variable")
+    ))?;
+    simple_page.insert("simple_component", components)?;
+
+    println!("{}", nest.render(&simple_page.into())?);
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! Callback wraps the functions passed in the options so that the options can
+//! still be cloned, debugged & shared between threads.
+
+use serde_json::{Map, Number, Value};
+use std::{fmt, ops::Deref, path::Path, sync::Arc};
+
+/// Shared function, `F` is a `dyn Fn` trait object.
+pub struct Callback<F: ?Sized>(pub Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Callback(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Callback(..)")
+    }
+}
+
+impl<F: ?Sized> Deref for Callback<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.0
+    }
+}
+
+/// Formats a number for the output.
+pub type NumberFormat = Callback<dyn Fn(&Number) -> String + Send + Sync>;
+
+/// Transforms the output of the outermost render.
+pub type PostRender = Callback<dyn Fn(String) -> String + Send + Sync>;
+
+/// Inspects or modifies the template hash before the template is rendered,
+/// receives the template name & a copy of the template hash.
+pub type PreRender = Callback<dyn Fn(&str, &mut Map<String, Value>) + Send + Sync>;
+
+/// Decides if the file found in the template directory walk is indexed.
+pub type FileFilter = Callback<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Called with the template name & the variable name when a variable has no
+/// value in the template hash or the defaults.
+pub type OnMissing = Callback<dyn Fn(&str, &str) + Send + Sync>;
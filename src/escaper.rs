@@ -0,0 +1,196 @@
+//! Escapers are applied to Value::String() input before it's substituted in
+//! the template.
+
+use html_escape::encode_safe;
+use std::{borrow::Cow, collections::HashSet, fmt, sync::Arc};
+
+/// Escapes the text for the target format of the template.
+#[derive(Clone, Default)]
+pub enum Escaper {
+    /// Escapes `&`, `<`, `>`, `"`, `'` and `/` as HTML entities.
+    #[default]
+    Html,
+
+    /// Escapes `&`, `<`, `>`, `"` and `'` as XML entities.
+    Xml,
+
+    /// Backslash-escapes the text for a JavaScript string literal, `<`, `>`,
+    /// `/`, quotes, backslashes & line terminators are escaped so the text
+    /// can't end the string or the `<script>` element.
+    Javascript,
+
+    /// Percent-encodes every byte of the text except the unreserved
+    /// characters of RFC 3986, i.e. letters, digits, `-`, `.`, `_` and `~`.
+    Url,
+
+    /// Escapes like Html except the tags in the set & their closing forms,
+    /// i.e. with `b` in the set `<b>bold</b>` is kept as is. Tag names are
+    /// matched case-insensitively & should be in lowercase. The tags are kept
+    /// with their attributes, it is not a sanitizer so don't allow tags whose
+    /// attributes can run scripts for untrusted input.
+    AllowTags(HashSet<String>),
+
+    /// Text is not escaped.
+    None,
+
+    /// Escapes the text with the given function.
+    Custom(Arc<dyn Fn(&str) -> String + Send + Sync>),
+}
+
+impl Escaper {
+    /// Returns the escaped text.
+    pub fn escape<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Escaper::Html => encode_safe(text),
+            Escaper::Xml => escape_xml(text),
+            Escaper::Javascript => escape_javascript(text),
+            Escaper::Url => escape_url(text),
+            Escaper::AllowTags(tags) => escape_allow_tags(text, tags),
+            Escaper::None => Cow::Borrowed(text),
+            Escaper::Custom(escape) => Cow::Owned(escape(text)),
+        }
+    }
+
+    /// Returns the escaper for the filter name used in templates.
+    pub(crate) fn from_filter(name: &str) -> Option<Escaper> {
+        match name {
+            "html" => Some(Escaper::Html),
+            "xml" => Some(Escaper::Xml),
+            "js" => Some(Escaper::Javascript),
+            "urlencode" => Some(Escaper::Url),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for Escaper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Escaper::Html => write!(f, "Html"),
+            Escaper::Xml => write!(f, "Xml"),
+            Escaper::Javascript => write!(f, "Javascript"),
+            Escaper::Url => write!(f, "Url"),
+            Escaper::AllowTags(tags) => write!(f, "AllowTags({:?})", tags),
+            Escaper::None => write!(f, "None"),
+            Escaper::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+fn escape_xml(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn escape_javascript(text: &str) -> Cow<'_, str> {
+    let special = [
+        '<', '>', '/', '"', '\'', '\\', '\n', '\r', '\u{2028}', '\u{2029}',
+    ];
+    if !text.contains(special) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("\\u003C"),
+            '>' => escaped.push_str("\\u003E"),
+            '/' => escaped.push_str("\\/"),
+            '"' => escaped.push_str("\\\""),
+            '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{2028}' => escaped.push_str("\\u2028"),
+            '\u{2029}' => escaped.push_str("\\u2029"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn escape_url(text: &str) -> Cow<'_, str> {
+    let unreserved = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~');
+    if text.bytes().all(unreserved) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len() * 3);
+    for b in text.bytes() {
+        match unreserved(b) {
+            true => escaped.push(b as char),
+            false => escaped.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn escape_allow_tags<'a>(text: &'a str, tags: &HashSet<String>) -> Cow<'a, str> {
+    let mut escaped = String::with_capacity(text.len());
+    // `rest` starts at a '<' or is the remaining text after the last tag.
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        escaped.push_str(&encode_safe(&rest[..start]));
+        rest = &rest[start..];
+        match allowed_tag_len(rest, tags) {
+            Some(len) => {
+                escaped.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+            None => {
+                escaped.push_str("&lt;");
+                rest = &rest[1..];
+            }
+        }
+    }
+    escaped.push_str(&encode_safe(rest));
+    match escaped == text {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(escaped),
+    }
+}
+
+/// Returns the length of the tag at the start of text if it's allowed. Closing
+/// tags must not have attributes.
+fn allowed_tag_len(text: &str, tags: &HashSet<String>) -> Option<usize> {
+    let len = text.find('>')? + 1;
+    let tag = &text[1..len - 1];
+    if tag.contains('<') {
+        return None;
+    }
+
+    let (closing, tag) = match tag.strip_prefix('/') {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    };
+    let name_len = tag
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(tag.len());
+    let (name, attributes) = tag.split_at(name_len);
+    let attributes_ok = match closing {
+        true => attributes.trim().is_empty(),
+        false => {
+            attributes.is_empty()
+                || attributes == "/"
+                || attributes.starts_with(char::is_whitespace)
+        }
+    };
+    match attributes_ok && tags.contains(&name.to_ascii_lowercase()) {
+        true => Some(len),
+        false => None,
+    }
+}
@@ -0,0 +1,136 @@
+//! Filling is a typed alternative to building template hashes with
+//! `serde_json::json!`, it can be modified after creation and converts into a
+//! `serde_json::Value` for rendering.
+
+use crate::TemplateNestError;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Represents a value that fills a template variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filling {
+    /// Plain text.
+    Text(String),
+
+    /// List of fillings, rendered one after the other.
+    List(Vec<Filling>),
+
+    /// Template hash, contains the name label & the variables.
+    Template(HashMap<String, Filling>),
+}
+
+impl Filling {
+    /// Inserts a variable into a template hash filling.
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<Filling>,
+    ) -> Result<(), TemplateNestError> {
+        match self {
+            Filling::Template(hash) => {
+                hash.insert(key.into(), value.into());
+                Ok(())
+            }
+            _ => Err(TemplateNestError::InvalidFillingOperation(format!(
+                "cannot insert into {} filling",
+                self.kind()
+            ))),
+        }
+    }
+
+    /// Appends a filling to a list filling.
+    pub fn push(&mut self, value: impl Into<Filling>) -> Result<(), TemplateNestError> {
+        match self {
+            Filling::List(list) => {
+                list.push(value.into());
+                Ok(())
+            }
+            _ => Err(TemplateNestError::InvalidFillingOperation(format!(
+                "cannot push into {} filling",
+                self.kind()
+            ))),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Filling::Text(_) => "text",
+            Filling::List(_) => "list",
+            Filling::Template(_) => "template",
+        }
+    }
+}
+
+impl From<&str> for Filling {
+    fn from(text: &str) -> Self {
+        Filling::Text(text.to_string())
+    }
+}
+
+impl From<String> for Filling {
+    fn from(text: String) -> Self {
+        Filling::Text(text)
+    }
+}
+
+impl From<Vec<Filling>> for Filling {
+    fn from(list: Vec<Filling>) -> Self {
+        Filling::List(list)
+    }
+}
+
+impl From<&Filling> for Value {
+    fn from(filling: &Filling) -> Self {
+        match filling {
+            Filling::Text(text) => Value::String(text.to_string()),
+            Filling::List(list) => Value::Array(list.iter().map(Value::from).collect()),
+            Filling::Template(hash) => Value::Object(
+                hash.iter()
+                    .map(|(k, v)| (k.to_string(), Value::from(v)))
+                    .collect::<Map<String, Value>>(),
+            ),
+        }
+    }
+}
+
+impl From<Filling> for Value {
+    fn from(filling: Filling) -> Self {
+        Value::from(&filling)
+    }
+}
+
+/// Creates a template hash filling.
+///
+/// ```rust
+/// use template_nest::filling;
+///
+/// let page = filling!(
+///     "TEMPLATE": "01-simple-component",
+///     "variable": "Simple Variable"
+/// );
+/// ```
+#[macro_export]
+macro_rules! filling {
+    ($($key:literal : $value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut hash = ::std::collections::HashMap::new();
+        $(hash.insert($key.to_string(), $crate::Filling::from($value));)*
+        $crate::Filling::Template(hash)
+    }};
+}
+
+/// Creates a list filling.
+#[macro_export]
+macro_rules! filling_list {
+    ($($value:expr),* $(,)?) => {
+        $crate::Filling::List(vec![$($crate::Filling::from($value)),*])
+    };
+}
+
+/// Creates a text filling.
+#[macro_export]
+macro_rules! filling_text {
+    ($text:expr) => {
+        $crate::Filling::Text($text.to_string())
+    };
+}
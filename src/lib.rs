@@ -52,14 +52,29 @@
 //! println!("{}", nest.render(&simple_page).unwrap());
 //! ```
 
-use html_escape::encode_safe;
+mod callback;
+mod escaper;
+mod filling;
+mod pretty;
+pub use callback::{Callback, FileFilter, NumberFormat, OnMissing, PostRender, PreRender};
+pub use escaper::Escaper;
+pub use filling::Filling;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use regex::Regex;
-use serde_json::Value;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
 use std::{
     borrow::Cow,
-    collections::{HashMap, HashSet},
-    fs, io,
-    path::{Path, PathBuf},
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt, fs, io,
+    ops::Range,
+    path::{Component, Path, PathBuf, MAIN_SEPARATOR},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
     time::SystemTime,
 };
 use thiserror::Error;
@@ -73,20 +88,115 @@ pub enum TemplateNestError {
     #[error("expected template file at `{0}`")]
     TemplateFileNotFound(String),
 
-    #[error("error reading: `{0}`")]
-    TemplateFileReadError(#[from] io::Error),
+    #[error("expected template file at `{missing}` (referenced by `{referenced_by}` in variable `{variable}`)")]
+    TemplateFileNotFoundIn {
+        missing: String,
+        referenced_by: String,
+        variable: String,
+    },
+
+    #[error("error reading `{path}`: {source}")]
+    TemplateFileReadError {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("error writing `{path}`: {source}")]
+    WriteError {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("encountered hash with no name label (name label: `{label}`, path: `{path}`)")]
+    NoNameLabel { label: String, path: String },
 
-    #[error("encountered hash with no name label (name label: `{0}`)")]
-    NoNameLabel(String),
+    #[error(
+        "encountered hash with invalid name label type (name label: `{label}`, value: `{value}`)"
+    )]
+    InvalidNameLabel { label: String, value: String },
 
-    #[error("encountered hash with invalid name label type (name label: `{0}`)")]
-    InvalidNameLabel(String),
+    #[error("expected template hash to be an object, got: `{0}`")]
+    InvalidTemplateHash(String),
+
+    #[error("error serializing template hash: {0}")]
+    Serialize(String),
 
     #[error("bad params in template hash, variable not present in template file: `{0}`")]
     BadParams(String),
+
+    #[error("variable in template file not present in template hash: `{0}`")]
+    MissingVariable(String),
+
+    #[error("conditional section is not closed or opened: `{0}`")]
+    UnmatchedConditional(String),
+
+    #[error("block is not closed or opened: `{0}`")]
+    UnmatchedBlock(String),
+
+    #[error("each loop is not of the form `each name with template`: `{0}`")]
+    InvalidEachLoop(String),
+
+    #[error("each loop over `{variable}` expects an object, got: `{value}`")]
+    InvalidEachValue { variable: String, value: String },
+
+    #[error("block `{fragment}` not found in template `{template}`")]
+    FragmentNotFound { template: String, fragment: String },
+
+    #[error("template name resolves outside the template directories: `{0}`")]
+    PathTraversal(String),
+
+    #[error("delimiters do not form a valid pattern: {0}")]
+    InvalidDelimiters(String),
+
+    #[error("unknown filter: `{0}`")]
+    UnknownFilter(String),
+
+    #[error("variable name does not match the variable name pattern: `{0}`")]
+    InvalidVariableName(String),
+
+    #[error("template hash exceeds the maximum depth of {0}")]
+    MaxDepthExceeded(usize),
+
+    #[error("circular template reference: `{}`", .0.join(" -> "))]
+    CircularReference(Vec<String>),
+
+    #[error("invalid operation on filling: {0}")]
+    InvalidFillingOperation(String),
+
+    #[error("error parsing defaults file `{path}`: {error}")]
+    InvalidDefaultsFile { path: String, error: String },
+
+    #[cfg(feature = "yaml")]
+    #[error("error parsing YAML: {0}")]
+    InvalidYaml(String),
+
+    #[cfg(feature = "toml")]
+    #[error("error parsing TOML: {0}")]
+    InvalidToml(String),
 }
 
+/// Key of the template hash that holds the block overrides.
+pub const BLOCKS_LABEL: &str = "BLOCKS";
+
+/// Variable that holds the name of the missing template in the fallback
+/// template.
+pub const MISSING_NAME_LABEL: &str = "MISSING_NAME";
+
+/// Variable that holds the key of the entry in an each loop template.
+pub const KEY_LABEL: &str = "KEY";
+
+/// Variable that holds the value of the entry in an each loop template.
+pub const VALUE_LABEL: &str = "VALUE";
+
 /// Options for TemplateNest.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct TemplateNestOption {
     /// Delimiters used in the template. It is a tuple of two strings,
     /// representing the start and end delimiters.
@@ -95,12 +205,48 @@ pub struct TemplateNestOption {
     /// Name label used to identify the template to be used.
     pub label: String,
 
+    /// Keys of the template hash that are not variables of the template, they
+    /// are allowed by `die_on_bad_params`. The name label, `BLOCKS` (if
+    /// `blocks` is set) and `KEY` & `VALUE` (if `each_loops` is set) are
+    /// always reserved. A template variable named like a reserved key is still
+    /// substituted with its value, i.e. `<!--% TEMPLATE %-->` renders the
+    /// template name. Default: Empty
+    pub reserved_keys: HashSet<String>,
+
+    /// Template used for a template hash that doesn't have the name label. If
+    /// None then it results in an error. Default: None
+    pub default_template: Option<String>,
+
+    /// Maps numeric name labels to template names, i.e. with `1` mapped to
+    /// `01-simple-component` the label can be `"TEMPLATE": 1`. Default: None
+    pub label_map: Option<HashMap<u64, String>>,
+
     /// Template extension, appended on label to identify the template.
     pub extension: String,
 
     /// Directory where templates are located.
     pub directory: PathBuf,
 
+    /// Additional directories where templates are located, layered over
+    /// `directory` in order. If a template exists in multiple directories
+    /// then the one in the last directory is used.
+    pub directories: Vec<PathBuf>,
+
+    /// If True, then symlinks are followed when walking the template
+    /// directories. Default: False
+    pub follow_symlinks: bool,
+
+    /// Maximum depth of the template directory walk, templates directly in
+    /// the directory are at depth 1. Default: None
+    pub max_walk_depth: Option<usize>,
+
+    /// If set then only the files in the template directory walk for which
+    /// it returns true are indexed, in addition to the extension check. It's
+    /// not consulted for templates looked up by name in lazy mode.
+    /// Default: None
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub file_filter: Option<FileFilter>,
+
     /// Prepend & Append a string to every template which is helpful in
     /// identifying which template the output text came from.
     pub show_labels: bool,
@@ -109,7 +255,18 @@ pub struct TemplateNestOption {
     /// '<!--', '-->'.
     pub comment_delimiters: (String, String),
 
-    /// Intended to improve readability when inspecting nested templates.
+    /// Used in conjunction with show_labels. If it's Some then only the
+    /// templates in the set are labelled. Default: None
+    pub label_templates: Option<HashSet<String>>,
+
+    /// Prefix & suffix added around the output of every template, outside of
+    /// the labels. Unlike show_labels the strings are added as is, they're not
+    /// put on their own line. Default: None
+    pub template_wrapper: Option<(String, String)>,
+
+    /// Intended to improve readability when inspecting nested templates. Every
+    /// line of a multi-line value is shifted by the indent of the variable,
+    /// the indentation within the value is kept.
     pub fixed_indent: bool,
 
     /// If True, then an attempt to populate a template with a variable that
@@ -117,34 +274,331 @@ pub struct TemplateNestOption {
     /// error.
     pub die_on_bad_params: bool,
 
+    /// If True, then a variable that is not provided by the template hash or
+    /// defaults is kept as is in the output instead of being replaced by an
+    /// empty string. Default: False
+    pub keep_unresolved: bool,
+
+    /// If True, then a value substituted from the defaults instead of the
+    /// template hash is surrounded by `DEFAULT name` & `END DEFAULT name`
+    /// comments in comment_delimiters, for finding the defaulted values.
+    /// Default: False
+    pub mark_defaults: bool,
+
+    /// If True, then a template that is rendered within itself results in an
+    /// error. Recursive templates (e.g. nested menus) legitimately do this so
+    /// it's disabled by default.
+    pub die_on_circular_reference: bool,
+
+    /// If True, then a variable in the template file that is not provided by
+    /// the template hash or defaults results in an error.
+    pub die_on_unused_variables: bool,
+
+    /// If True, then a template whose file doesn't exist is rendered as an
+    /// empty string instead of returning an error. Default: False
+    pub missing_template_is_empty: bool,
+
+    /// Template rendered in place of a template whose file doesn't exist, the
+    /// name of the missing template is available as the `MISSING_NAME`
+    /// variable. Takes precedence over `missing_template_is_empty`.
+    /// Default: None
+    pub fallback_template: Option<String>,
+
     /// Escapes a token delimiter, i.e. if set to '\' then prefixing the token
     /// delimiters with '\' means it won't be considered a variable.
     ///
     /// <!--% token %-->  => is a variable
     /// \<!--% token %--> => is not a variable. ('\' is removed from output)
+    ///
+    /// An end delimiter prefixed with it inside a token doesn't end the token,
+    /// the '\' is removed from the variable name. Outside of a token the '\'
+    /// is removed from output.
+    ///
+    /// <!--% a \%--> b %--> => is the variable 'a %--> b'
+    /// 50\%-->              => is '50%-->'
     pub token_escape_char: String,
 
+    /// Prefix & Suffix that mark a token as a comment, i.e. if set to '#', '#'
+    /// then `<!--%# note #%-->` is removed from the output. Default: None
+    pub comment_markers: Option<(String, String)>,
+
+    /// Marker that trims the whitespace around a token, i.e. if set to '-' then
+    /// `<!--%- name %-->` trims the whitespace before the token and
+    /// `<!--% name -%-->` trims the whitespace after the token. Default: ""
+    pub trim_marker: String,
+
     /// Provide a hash of default values that are substituted if template hash
-    /// does not provide a value.
+    /// does not provide a value. A key of the form `template/variable` only
+    /// applies to the variable within that template and takes precedence over
+    /// the bare variable name.
     pub defaults: HashMap<String, Value>,
 
-    /// If True, then all Value::String() input is escaped. Default: True
+    /// Provide a hash of default values per template, keyed by template name.
+    /// These take precedence over `defaults`.
+    pub template_defaults: HashMap<String, HashMap<String, Value>>,
+
+    /// If True, then all Value::String() input is escaped with `escaper`.
+    /// Default: True
     pub escape_html: bool,
+
+    /// Escaper used for Value::String() input. Default: Escaper::Html
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub escaper: Escaper,
+
+    /// Variables prefixed with this string are not escaped, i.e. if set to '!'
+    /// then the value of `<!--% !content %-->` is not escaped. Default: ""
+    pub raw_prefix: String,
+
+    /// If True, then the filters after the variable name separated by `|` are
+    /// applied to the value instead of `escaper`, i.e. `<!--% name | js %-->`.
+    /// Filters are applied in order to Value::String() input even if
+    /// `escape_html` is False or the variable is raw. Available filters:
+    /// `html`, `xml`, `js` & `urlencode`. Default: False
+    pub filters: bool,
+
+    /// An object with only this key & a string value is rendered as the
+    /// string without escaping, i.e. `{ "RAW": "<b>bold</b>" }`. Set it to ""
+    /// to disable. Default: "RAW"
+    pub raw_key: String,
+
+    /// If set then every variable name must match the pattern, anchor it to
+    /// match the whole name, i.e. `^[a-z_]+$`. Default: None
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub variable_name_pattern: Option<Regex>,
+
+    /// If True, then `<!--% if:name %-->` & `<!--% endif:name %-->` tokens
+    /// mark a conditional section, it's removed from the output if `name` is
+    /// absent, false or null. Default: False
+    pub conditionals: bool,
+
+    /// If True, then `<!--% block name %-->` & `<!--% endblock %-->` tokens
+    /// mark an overridable region, the contents between them are the default.
+    /// The region is replaced by the value of `name` in the `BLOCKS` object of
+    /// the template hash if it's present. Default: False
+    pub blocks: bool,
+
+    /// If True, then `<!--% each name with template %-->` renders the template
+    /// once for every entry of the object `name`, the entry is available in
+    /// the template as the `KEY` & `VALUE` variables. Default: False
+    pub each_loops: bool,
+
+    /// If True, then `<!--% classes name1 name2 ... %-->` joins the classes in
+    /// the variables with spaces, duplicates are dropped. A variable can be a
+    /// string of space separated classes or an array of them, every other
+    /// value including a missing variable is skipped. Default: False
+    pub classes: bool,
+
+    /// If True, then Value::Bool() is rendered as "true" or "false", otherwise
+    /// it's rendered as an empty string. Value::Null is always rendered as an
+    /// empty string. Default: True
+    pub bool_as_text: bool,
+
+    /// If True, then Value::Bool() is rendered as the name of the variable if
+    /// it's true and as an empty string if it's false, i.e. `<!--% checked %-->`
+    /// renders as `checked`. Useful for toggling HTML attributes, the name is
+    /// not escaped so `escape_html` has no effect on it. Takes precedence over
+    /// `bool_as_text`. Default: False
+    pub bool_as_attribute: bool,
+
+    /// Formats Value::Number() input, if it's None then the number is
+    /// rendered as is. Default: None
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub number_format: Option<NumberFormat>,
+
+    /// If True, then templates are indexed when they're first rendered instead
+    /// of indexing the whole directory on initialization. Default: False
+    pub lazy: bool,
+
+    /// If True, then invalid UTF-8 in template files is replaced by the
+    /// replacement character instead of returning an error. Default: False
+    pub lossy_utf8: bool,
+
+    /// Maximum depth of nested Arrays & Objects in the template hash.
+    /// Default: 256
+    pub max_depth: usize,
+
+    /// Separator inserted between the rendered elements of an array.
+    /// Default: None
+    pub array_separator: Option<String>,
+
+    /// Capacity reserved for the output of the outermost template, if it's
+    /// None then it's estimated from the template & the variables in the
+    /// template hash. Default: None
+    pub capacity_hint: Option<usize>,
+
+    /// If True, then trailing whitespace is removed from the rendered output.
+    /// Only the output of the outermost template is trimmed, nested templates
+    /// only drop the newline at the end of the file. Default: True
+    pub trim_trailing: bool,
+
+    /// If True, then leading whitespace is removed from the rendered output.
+    /// Like `trim_trailing` only the output of the outermost template is
+    /// trimmed, both can be set to trim the output on either end.
+    /// Default: False
+    pub trim_leading: bool,
+
+    /// If True, then runs of blank lines in the output are collapsed into a
+    /// single blank line. Default: False
+    pub collapse_blank_lines: bool,
 }
 
 /// Renders a template hash to produce an output.
+///
+/// TemplateNest is `Send + Sync`, the cache is behind a RwLock so it can be
+/// shared between threads with an `Arc` & rendered from concurrently.
 pub struct TemplateNest {
     option: TemplateNestOption,
 
+    /// Captures the variables in a template, compiled once from delimiters.
+    token_regex: Regex,
+
+    /// Captures the end delimiter of an escaped end delimiter, compiled once
+    /// from delimiters. None if token_escape_char is not set.
+    escaped_end_regex: Option<Regex>,
+
     /// Stores the indexed file in memory.
-    cache: HashMap<String, TemplateFileIndex>,
+    cache: RwLock<HashMap<String, Arc<TemplateFileIndex>>>,
+
+    /// Applied to every template hash before it's rendered.
+    pre_render: Option<PreRender>,
+
+    /// Applied to the output of the outermost render.
+    post_render: Option<PostRender>,
+
+    /// Called for every variable that resolves to nothing.
+    on_missing: Option<OnMissing>,
+}
+
+/// Clones share the indexed template contents, the cache itself is copied so
+/// that reindexing one doesn't affect the other.
+impl Clone for TemplateNest {
+    fn clone(&self) -> Self {
+        TemplateNest {
+            option: self.option.clone(),
+            token_regex: self.token_regex.clone(),
+            escaped_end_regex: self.escaped_end_regex.clone(),
+            cache: RwLock::new(self.cache.read().unwrap().clone()),
+            pre_render: self.pre_render.clone(),
+            post_render: self.post_render.clone(),
+            on_missing: self.on_missing.clone(),
+        }
+    }
+}
+
+/// Template contents are omitted, only the number of cached templates is
+/// shown.
+impl fmt::Debug for TemplateNest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TemplateNest")
+            .field("option", &self.option)
+            .field("cached_templates", &self.cache.read().unwrap().len())
+            .field("pre_render", &self.pre_render)
+            .field("post_render", &self.post_render)
+            .field("on_missing", &self.on_missing)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Statistics of the templates in cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Number of templates.
+    pub templates: usize,
+
+    /// Total size of the templates in bytes.
+    pub bytes: usize,
+
+    /// Total number of variables in the templates.
+    pub variables: usize,
+}
+
+/// Token in a template file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateToken {
+    /// Variable name, empty for escaped & comment tokens.
+    pub name: String,
+
+    /// Start & End byte positions of the token in the template file. With
+    /// `trim_marker` the span includes the trimmed whitespace, with
+    /// `lossy_utf8` the positions after an invalid sequence are in the lossy
+    /// contents.
+    pub start: usize,
+    pub end: usize,
+
+    pub kind: TemplateTokenKind,
+}
+
+/// Kind of a token in a template file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateTokenKind {
+    Variable,
+
+    /// Escape character before a token or an end delimiter, the span covers
+    /// only the escape character.
+    Escaped,
+    Comment,
+    If,
+    EndIf,
+    Block,
+    EndBlock,
+    Each,
+    Classes,
+}
+
+/// State carried through the recursive render calls.
+#[derive(Default)]
+struct RenderContext {
+    /// Number of nested Arrays & Objects currently being rendered.
+    depth: usize,
+
+    /// Names of the templates currently being rendered, from the outermost to
+    /// the innermost.
+    templates: Vec<String>,
+
+    /// Names of all the templates rendered, only collected if it's Some.
+    deps: Option<BTreeSet<String>>,
+
+    /// Overrides the name label of the options if it's Some.
+    label: Option<String>,
+
+    /// Keys & array indices traversed from the outermost template hash to the
+    /// value being rendered, i.e. `.nav[1]`.
+    path: Vec<String>,
+}
+
+impl RenderContext {
+    /// Returns the path to the value being rendered, `$` is the outermost
+    /// template hash.
+    fn breadcrumb(&self) -> String {
+        format!("${}", self.path.concat())
+    }
+
+    /// Returns a context for rendering in parallel, deps of the returned
+    /// context must be merged back with `join`.
+    #[cfg(feature = "rayon")]
+    fn fork(&self) -> Self {
+        RenderContext {
+            depth: self.depth,
+            templates: self.templates.clone(),
+            deps: self.deps.as_ref().map(|_| BTreeSet::new()),
+            label: self.label.clone(),
+            path: self.path.clone(),
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    fn join(&mut self, other: Self) {
+        if let (Some(deps), Some(other_deps)) = (&mut self.deps, other.deps) {
+            deps.extend(other_deps);
+        }
+    }
 }
 
 /// Represents an indexed template file.
 #[derive(Debug, Clone)]
 struct TemplateFileIndex {
-    /// Contents of the file.
-    contents: String,
+    /// Contents of the file, cloning the index does not copy the contents.
+    contents: Arc<str>,
 
     last_modified: SystemTime,
 
@@ -153,6 +607,11 @@ struct TemplateFileIndex {
 
     /// Variable names in the template file.
     variable_names: HashSet<String>,
+
+    /// Bytes removed from the start of the file before it's indexed, i.e. the
+    /// BOM & the delimiters directive. Positions in contents are this much
+    /// behind the positions in the file.
+    file_offset: usize,
 }
 
 /// Represents the variables in a template file.
@@ -165,307 +624,2293 @@ struct TemplateFileVariable {
     start_position: usize,
     end_position: usize,
 
-    /// Indent level of the variable.
-    indent_level: usize,
+    /// Indent of the variable, it reproduces the line up to the variable with
+    /// tabs kept & every other character replaced by a space.
+    indent: String,
+
+    kind: TokenKind,
+
+    /// If true then the value of this variable is not escaped.
+    raw: bool,
+
+    /// Filters applied to the value instead of the escaper, they're parsed
+    /// once when the template is indexed.
+    filters: Vec<Escaper>,
+}
+
+/// Represents the kind of token in a template file.
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// Variable that is substituted by the value in template hash.
+    Variable,
+
+    /// Token was escaped with token_escape_char, we just need to remove the
+    /// escape character.
+    Escaped,
+
+    /// Comment that is removed from the output.
+    Comment,
 
-    /// If true then this variable was escaped with token_escape_char, we just
-    /// need to remove the escape character.
-    escaped_token: bool,
+    /// Start of a conditional section, holds the index of the matching EndIf
+    /// token.
+    If(usize),
+
+    /// End of a conditional section, holds the index of the matching If token.
+    EndIf(usize),
+
+    /// Start of a block, holds the index of the matching EndBlock token.
+    Block(usize),
+
+    /// End of a block, holds the index of the matching Block token.
+    EndBlock(usize),
+
+    /// Loop over the entries of an object, holds the name of the template
+    /// rendered for every entry.
+    Each(String),
+
+    /// Classes merged from the variables, holds the names of the variables.
+    Classes(Vec<String>),
 }
 
 impl Default for TemplateNestOption {
     fn default() -> Self {
         TemplateNestOption {
             label: "TEMPLATE".to_string(),
+            reserved_keys: HashSet::new(),
             extension: "html".to_string(),
+            default_template: None,
+            label_map: None,
             show_labels: false,
+            label_templates: None,
+            template_wrapper: None,
             fixed_indent: false,
             die_on_bad_params: false,
+            die_on_unused_variables: false,
+            missing_template_is_empty: false,
+            fallback_template: None,
+            die_on_circular_reference: false,
+            keep_unresolved: false,
+            mark_defaults: false,
             directory: "templates".into(),
+            directories: vec![],
+            follow_symlinks: false,
+            max_walk_depth: None,
+            file_filter: None,
             delimiters: ("<!--%".to_string(), "%-->".to_string()),
             comment_delimiters: ("<!--".to_string(), "-->".to_string()),
             token_escape_char: "".to_string(),
+            comment_markers: None,
+            trim_marker: "".to_string(),
             defaults: HashMap::new(),
+            template_defaults: HashMap::new(),
             escape_html: true,
+            escaper: Escaper::Html,
+            raw_prefix: "".to_string(),
+            filters: false,
+            raw_key: "RAW".to_string(),
+            variable_name_pattern: None,
+            conditionals: false,
+            blocks: false,
+            each_loops: false,
+            classes: false,
+            bool_as_text: true,
+            bool_as_attribute: false,
+            number_format: None,
+            lazy: false,
+            lossy_utf8: false,
+            max_depth: 256,
+            array_separator: None,
+            capacity_hint: None,
+            trim_trailing: true,
+            trim_leading: false,
+            collapse_blank_lines: false,
         }
     }
 }
 
-impl TemplateNest {
-    pub fn new(option: TemplateNestOption) -> Result<Self, TemplateNestError> {
-        if !option.directory.is_dir() {
-            return Err(TemplateNestError::TemplateDirNotFound(
-                option.directory.display().to_string(),
-            ));
+impl TemplateNestOption {
+    /// Returns a builder initialized with the default options.
+    pub fn builder() -> TemplateNestOptionBuilder {
+        TemplateNestOptionBuilder::default()
+    }
+
+    /// Returns the default options with every check that enforces correctness
+    /// turned on, i.e. `die_on_bad_params`, `die_on_unused_variables` &
+    /// `die_on_circular_reference` are set. Template names that resolve
+    /// outside the template directories are always an error.
+    pub fn strict() -> Self {
+        TemplateNestOption {
+            die_on_bad_params: true,
+            die_on_unused_variables: true,
+            die_on_circular_reference: true,
+            ..Default::default()
         }
+    }
 
-        let mut cache = HashMap::new();
-        for entry in WalkDir::new(&option.directory)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| match e.metadata() {
-                Ok(m) => {
-                    // entry must be a file and the file name must end with option.extension
-                    m.is_file() && e.file_name().to_string_lossy().ends_with(&option.extension)
+    /// Reads defaults from a JSON file, the file must contain an object that
+    /// maps variable names to their values.
+    pub fn defaults_from_file(
+        path: impl AsRef<Path>,
+    ) -> Result<HashMap<String, Value>, TemplateNestError> {
+        let path = path.as_ref();
+        let contents =
+            fs::read_to_string(path).map_err(|err| TemplateNestError::InvalidDefaultsFile {
+                path: path.display().to_string(),
+                error: err.to_string(),
+            })?;
+        serde_json::from_str(&contents).map_err(|err| TemplateNestError::InvalidDefaultsFile {
+            path: path.display().to_string(),
+            error: err.to_string(),
+        })
+    }
+
+    /// Reads defaults from the environment variables that start with prefix,
+    /// the prefix is stripped & the rest is lowercased to get the variable
+    /// name. i.e. with prefix `SITE_`, `SITE_CDN_URL` is `cdn_url`. The
+    /// variable named exactly prefix is skipped.
+    pub fn defaults_from_env(prefix: &str) -> HashMap<String, Value> {
+        // Variables that are not valid UTF-8 are skipped.
+        std::env::vars_os()
+            .filter_map(|(key, value)| {
+                let (key, value) = (key.into_string().ok()?, value.into_string().ok()?);
+                let name = key.strip_prefix(prefix)?.to_lowercase();
+                match name.is_empty() {
+                    true => None,
+                    false => Some((name, Value::String(value))),
                 }
-                Err(_) => false,
             })
-        {
-            let file_name = entry
-                .path()
-                .strip_prefix(&option.directory)
-                .unwrap()
-                .to_string_lossy();
+            .collect()
+    }
 
-            let file_name = if option.extension.is_empty() {
-                &file_name
-            } else {
-                file_name
-                    .strip_suffix(&format!(".{}", &option.extension))
-                    .unwrap()
-            };
+    /// Returns `directory` followed by `directories`.
+    fn template_dirs(&self) -> impl DoubleEndedIterator<Item = &PathBuf> {
+        std::iter::once(&self.directory).chain(self.directories.iter())
+    }
+}
 
-            // Index the templates and store in cache.
-            cache.insert(file_name.to_string(), Self::index(&option, entry.path())?);
-        }
+/// Builder for TemplateNestOption, every setter overrides the corresponding
+/// default value.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateNestOptionBuilder {
+    option: TemplateNestOption,
+}
 
-        Ok(Self { option, cache })
+impl TemplateNestOptionBuilder {
+    pub fn delimiters(mut self, start: impl Into<String>, end: impl Into<String>) -> Self {
+        self.option.delimiters = (start.into(), end.into());
+        self
     }
 
-    fn template_name_to_file(option: &TemplateNestOption, template_name: &str) -> PathBuf {
-        let file_name = if option.extension.is_empty() {
-            template_name.to_string()
-        } else {
-            format!("{}.{}", template_name, option.extension)
-        };
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.option.label = label.into();
+        self
+    }
 
-        option.directory.join(file_name)
+    pub fn reserved_keys(mut self, reserved_keys: HashSet<String>) -> Self {
+        self.option.reserved_keys = reserved_keys;
+        self
     }
 
-    /// Given a template name, returns the "index" of the template file, it
-    /// contains the contents of the file and all the variables that are
-    /// present.
-    fn index(
-        option: &TemplateNestOption,
-        template_file: &Path,
-    ) -> Result<TemplateFileIndex, TemplateNestError> {
-        if !template_file.is_file() {
-            return Err(TemplateNestError::TemplateFileNotFound(
-                template_file.display().to_string(),
-            ));
-        }
+    pub fn default_template(mut self, default_template: impl Into<String>) -> Self {
+        self.option.default_template = Some(default_template.into());
+        self
+    }
 
-        let contents = match fs::read_to_string(template_file) {
-            Ok(file_contents) => file_contents,
-            Err(err) => {
-                return Err(TemplateNestError::TemplateFileReadError(err));
-            }
-        };
+    pub fn label_map(mut self, label_map: HashMap<u64, String>) -> Self {
+        self.option.label_map = Some(label_map);
+        self
+    }
 
-        let mut variable_names = HashSet::new();
-        let mut variables = vec![];
-        // Capture all the variables in the template.
-        let re = Regex::new(&format!(
-            "{}(.+?){}",
-            option.delimiters.0, option.delimiters.1
-        ))
-        .unwrap();
-        for cap in re.captures_iter(&contents) {
-            let whole_capture = cap.get(0).unwrap();
-            let start_position = whole_capture.start();
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.option.extension = extension.into();
+        self
+    }
 
-            // If token_escape_char is set then look behind for it and if we
-            // find the escape char then we're only going to remove the escape
-            // char and not remove this variable.
-            //
-            // The variable can be at the beginning of the file, that will mean
-            // calculating escape_char_start results in an overflow.
-            if !option.token_escape_char.is_empty()
-                && start_position > option.token_escape_char.len()
-            {
-                let escape_char_start = start_position - option.token_escape_char.len();
-                if contents[escape_char_start..start_position] == option.token_escape_char {
-                    variables.push(TemplateFileVariable {
-                        indent_level: 0,
-                        name: "".to_string(),
-                        escaped_token: true,
-                        start_position: escape_char_start,
-                        end_position: escape_char_start + option.token_escape_char.len(),
-                    });
-                    continue;
-                }
-            }
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.option.directory = directory.into();
+        self
+    }
 
-            // If fixed_indent is enable then record the indent level for this
-            // variable. To get the indent level we look at each character in
-            // reverse from the start position of the variable until we find a
-            // newline character.
-            let indent_level = match option.fixed_indent {
-                true => {
-                    // If we do not encounter a newline then that means this
-                    // variable is on the first line, we take the start_position
-                    // as the indent_level.
-                    let newline_position = &contents[..start_position].rfind('\n');
-                    match newline_position {
-                        Some(position) => start_position - position - 1,
-                        None => start_position,
-                    }
-                }
-                false => 0,
-            };
+    pub fn directories(mut self, directories: Vec<PathBuf>) -> Self {
+        self.option.directories = directories;
+        self
+    }
 
-            let variable_name = cap[1].trim();
-            variable_names.insert(variable_name.to_string());
-            variables.push(TemplateFileVariable {
-                indent_level,
-                start_position,
-                end_position: whole_capture.end(),
-                name: variable_name.to_string(),
-                escaped_token: false,
-            });
-        }
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.option.follow_symlinks = follow_symlinks;
+        self
+    }
 
-        let last_modified = template_file.metadata().unwrap().modified().unwrap();
-        let file_index = TemplateFileIndex {
-            variable_names,
-            contents,
-            variables,
-            last_modified,
-        };
-        Ok(file_index)
+    pub fn max_walk_depth(mut self, max_walk_depth: usize) -> Self {
+        self.option.max_walk_depth = Some(max_walk_depth);
+        self
     }
 
-    /// Given a TemplateHash, it parses the TemplateHash and renders a String
-    /// output.
-    pub fn render(&self, to_render: &Value) -> Result<String, TemplateNestError> {
-        match to_render {
-            Value::Null => Ok("".to_string()),
-            Value::Bool(x) => Ok(x.to_string()),
-            Value::String(x) => Ok(x.to_string()),
-            Value::Number(x) => Ok(x.to_string()),
-            Value::Array(t_array) => {
-                let mut render = "".to_string();
-                for t in t_array {
-                    render.push_str(&self.render(t)?);
-                }
-                Ok(render)
-            }
-            Value::Object(t_hash) => {
-                let t_label: &Value =
-                    t_hash
-                        .get(&self.option.label)
-                        .ok_or(TemplateNestError::NoNameLabel(
-                            self.option.label.to_string(),
-                        ))?;
-
-                // template name/path must contain a string.
-                let t_path = match t_label {
-                    Value::String(path) => path,
-                    _ => {
-                        return Err(TemplateNestError::InvalidNameLabel(
-                            self.option.label.to_string(),
-                        ))
-                    }
-                };
+    pub fn file_filter(
+        mut self,
+        file_filter: impl Fn(&Path) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.option.file_filter = Some(Callback(Arc::new(file_filter)));
+        self
+    }
 
-                let t_file = Self::template_name_to_file(&self.option, t_path);
-                let t_index: Cow<TemplateFileIndex> = match self.cache.get(t_path) {
-                    Some(index) => {
-                        // If the file has been modified then get the latest
-                        // index.
-                        let last_modified = t_file.metadata().unwrap().modified().unwrap();
-
-                        if last_modified > index.last_modified {
-                            Cow::Owned(Self::index(&self.option, t_file.as_path())?)
-                        } else {
-                            Cow::Borrowed(index)
-                        }
-                    }
-                    None => Cow::Owned(Self::index(&self.option, t_file.as_path())?),
-                };
+    pub fn show_labels(mut self, show_labels: bool) -> Self {
+        self.option.show_labels = show_labels;
+        self
+    }
 
-                if self.option.die_on_bad_params {
-                    for var_name in t_hash.keys() {
-                        // If a variable in t_hash is not present in the
-                        // template file and it's not the template label then
-                        // it's a bad param.
-                        if !t_index.variable_names.contains(var_name)
-                            && var_name != &self.option.label
-                        {
-                            return Err(TemplateNestError::BadParams(var_name.to_string()));
-                        }
-                    }
-                }
+    pub fn comment_delimiters(mut self, start: impl Into<String>, end: impl Into<String>) -> Self {
+        self.option.comment_delimiters = (start.into(), end.into());
+        self
+    }
 
-                let mut rendered = String::from(&t_index.contents);
+    pub fn label_templates(mut self, label_templates: HashSet<String>) -> Self {
+        self.option.label_templates = Some(label_templates);
+        self
+    }
 
-                // Iterate through all variables in reverse. We do this because
-                // we don't want to mess up all the indexed positions.
-                for var in t_index.variables.iter().rev() {
-                    // If the variable was escaped then we just remove the
-                    // token, not the variable.
-                    if var.escaped_token {
-                        rendered.replace_range(var.start_position..var.end_position, "");
-                        continue;
-                    }
+    pub fn template_wrapper(
+        mut self,
+        prefix: impl Into<String>,
+        suffix: impl Into<String>,
+    ) -> Self {
+        self.option.template_wrapper = Some((prefix.into(), suffix.into()));
+        self
+    }
 
-                    // If the variable doesn't exist in template hash then
-                    // replace it by an empty string.
-                    let mut render = "".to_string();
-
-                    // Look for the variable in t_hash, if it's not provided
-                    // then we look at defaults HashMap, and then considering
-                    // variable namespacing.
-                    if let Some(value) = t_hash
-                        .get(&var.name)
-                        .or_else(|| self.option.defaults.get(&var.name))
-                    {
-                        let mut r: String = match value {
-                            Value::String(text) => encode_safe(text).to_string(),
-                            _ => self.render(value)?,
-                        };
-
-                        // If fixed_indent is set then get the indent level and
-                        // replace all newlines in the rendered string.
-                        if self.option.fixed_indent && var.indent_level != 0 {
-                            let replacement = format!("\n{}", " ".repeat(var.indent_level));
-                            r = r.replace('\n', &replacement);
-                        }
+    pub fn fixed_indent(mut self, fixed_indent: bool) -> Self {
+        self.option.fixed_indent = fixed_indent;
+        self
+    }
 
-                        render.push_str(&r);
-                    }
+    pub fn die_on_bad_params(mut self, die_on_bad_params: bool) -> Self {
+        self.option.die_on_bad_params = die_on_bad_params;
+        self
+    }
 
-                    rendered.replace_range(var.start_position..var.end_position, &render);
-                }
+    pub fn die_on_unused_variables(mut self, die_on_unused_variables: bool) -> Self {
+        self.option.die_on_unused_variables = die_on_unused_variables;
+        self
+    }
 
-                // Add lables to the rendered string if show_labels is true.
-                if self.option.show_labels {
-                    rendered.replace_range(
-                        0..0,
-                        &format!(
-                            "{} BEGIN {} {}\n",
-                            self.option.comment_delimiters.0,
-                            t_path,
-                            self.option.comment_delimiters.1
-                        ),
-                    );
-                    rendered.replace_range(
-                        rendered.len()..rendered.len(),
-                        &format!(
-                            "{} END {} {}\n",
-                            self.option.comment_delimiters.0,
-                            t_path,
-                            self.option.comment_delimiters.1
-                        ),
-                    );
-                }
+    pub fn missing_template_is_empty(mut self, missing_template_is_empty: bool) -> Self {
+        self.option.missing_template_is_empty = missing_template_is_empty;
+        self
+    }
 
-                // Trim trailing without cloning `rendered'.
-                let len_withoutcrlf = rendered.trim_end().len();
-                rendered.truncate(len_withoutcrlf);
+    pub fn fallback_template(mut self, fallback_template: impl Into<String>) -> Self {
+        self.option.fallback_template = Some(fallback_template.into());
+        self
+    }
 
-                Ok(rendered)
-            }
-        }
+    pub fn die_on_circular_reference(mut self, die_on_circular_reference: bool) -> Self {
+        self.option.die_on_circular_reference = die_on_circular_reference;
+        self
+    }
+
+    pub fn keep_unresolved(mut self, keep_unresolved: bool) -> Self {
+        self.option.keep_unresolved = keep_unresolved;
+        self
+    }
+
+    pub fn mark_defaults(mut self, mark_defaults: bool) -> Self {
+        self.option.mark_defaults = mark_defaults;
+        self
     }
+
+    pub fn token_escape_char(mut self, token_escape_char: impl Into<String>) -> Self {
+        self.option.token_escape_char = token_escape_char.into();
+        self
+    }
+
+    pub fn comment_markers(mut self, prefix: impl Into<String>, suffix: impl Into<String>) -> Self {
+        self.option.comment_markers = Some((prefix.into(), suffix.into()));
+        self
+    }
+
+    pub fn trim_marker(mut self, trim_marker: impl Into<String>) -> Self {
+        self.option.trim_marker = trim_marker.into();
+        self
+    }
+
+    pub fn defaults(mut self, defaults: HashMap<String, Value>) -> Self {
+        self.option.defaults = defaults;
+        self
+    }
+
+    pub fn template_defaults(
+        mut self,
+        template_defaults: HashMap<String, HashMap<String, Value>>,
+    ) -> Self {
+        self.option.template_defaults = template_defaults;
+        self
+    }
+
+    pub fn escape_html(mut self, escape_html: bool) -> Self {
+        self.option.escape_html = escape_html;
+        self
+    }
+
+    pub fn escaper(mut self, escaper: Escaper) -> Self {
+        self.option.escaper = escaper;
+        self
+    }
+
+    pub fn raw_prefix(mut self, raw_prefix: impl Into<String>) -> Self {
+        self.option.raw_prefix = raw_prefix.into();
+        self
+    }
+
+    pub fn filters(mut self, filters: bool) -> Self {
+        self.option.filters = filters;
+        self
+    }
+
+    pub fn raw_key(mut self, raw_key: impl Into<String>) -> Self {
+        self.option.raw_key = raw_key.into();
+        self
+    }
+
+    pub fn variable_name_pattern(mut self, variable_name_pattern: Regex) -> Self {
+        self.option.variable_name_pattern = Some(variable_name_pattern);
+        self
+    }
+
+    pub fn conditionals(mut self, conditionals: bool) -> Self {
+        self.option.conditionals = conditionals;
+        self
+    }
+
+    pub fn blocks(mut self, blocks: bool) -> Self {
+        self.option.blocks = blocks;
+        self
+    }
+
+    pub fn each_loops(mut self, each_loops: bool) -> Self {
+        self.option.each_loops = each_loops;
+        self
+    }
+
+    pub fn classes(mut self, classes: bool) -> Self {
+        self.option.classes = classes;
+        self
+    }
+
+    pub fn bool_as_text(mut self, bool_as_text: bool) -> Self {
+        self.option.bool_as_text = bool_as_text;
+        self
+    }
+
+    pub fn bool_as_attribute(mut self, bool_as_attribute: bool) -> Self {
+        self.option.bool_as_attribute = bool_as_attribute;
+        self
+    }
+
+    pub fn number_format(
+        mut self,
+        number_format: impl Fn(&Number) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.option.number_format = Some(Callback(Arc::new(number_format)));
+        self
+    }
+
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.option.lazy = lazy;
+        self
+    }
+
+    pub fn lossy_utf8(mut self, lossy_utf8: bool) -> Self {
+        self.option.lossy_utf8 = lossy_utf8;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.option.max_depth = max_depth;
+        self
+    }
+
+    pub fn array_separator(mut self, array_separator: impl Into<String>) -> Self {
+        self.option.array_separator = Some(array_separator.into());
+        self
+    }
+
+    pub fn capacity_hint(mut self, capacity_hint: usize) -> Self {
+        self.option.capacity_hint = Some(capacity_hint);
+        self
+    }
+
+    pub fn trim_trailing(mut self, trim_trailing: bool) -> Self {
+        self.option.trim_trailing = trim_trailing;
+        self
+    }
+
+    pub fn trim_leading(mut self, trim_leading: bool) -> Self {
+        self.option.trim_leading = trim_leading;
+        self
+    }
+
+    pub fn collapse_blank_lines(mut self, collapse_blank_lines: bool) -> Self {
+        self.option.collapse_blank_lines = collapse_blank_lines;
+        self
+    }
+
+    pub fn build(self) -> TemplateNestOption {
+        self.option
+    }
+}
+
+impl TemplateNest {
+    /// Creates a TemplateNest with default options and the given template
+    /// directory.
+    pub fn with_directory(directory: impl Into<PathBuf>) -> Result<Self, TemplateNestError> {
+        Self::new(TemplateNestOption {
+            directory: directory.into(),
+            ..Default::default()
+        })
+    }
+
+    pub fn new(option: TemplateNestOption) -> Result<Self, TemplateNestError> {
+        for directory in option.template_dirs() {
+            if !directory.is_dir() {
+                return Err(TemplateNestError::TemplateDirNotFound(
+                    directory.display().to_string(),
+                ));
+            }
+        }
+
+        let token_regex = Self::token_regex(&option)?;
+        let escaped_end_regex = Self::escaped_end_regex(&option)?;
+        let cache = Self::build_cache(&option, &token_regex, escaped_end_regex.as_ref())?;
+        Ok(Self {
+            option,
+            token_regex,
+            escaped_end_regex,
+            cache: RwLock::new(cache),
+            pre_render: None,
+            post_render: None,
+            on_missing: None,
+        })
+    }
+
+    /// Sets a function that's called with the template name & a copy of the
+    /// template hash before every template is rendered, the template is
+    /// rendered with the modified copy. The hash is not copied if it's unset.
+    pub fn set_pre_render(
+        &mut self,
+        pre_render: impl Fn(&str, &mut Map<String, Value>) + Send + Sync + 'static,
+    ) {
+        self.pre_render = Some(Callback(Arc::new(pre_render)));
+    }
+
+    /// Sets a function that transforms the output, it runs once per render
+    /// on the output of the outermost template & not on the nested templates.
+    pub fn set_post_render(
+        &mut self,
+        post_render: impl Fn(String) -> String + Send + Sync + 'static,
+    ) {
+        self.post_render = Some(Callback(Arc::new(post_render)));
+    }
+
+    /// Sets a function that's called with the template name & the variable
+    /// name whenever a variable has no value in the template hash or the
+    /// defaults, i.e. to log them. The variable is rendered as before.
+    pub fn set_on_missing(&mut self, on_missing: impl Fn(&str, &str) + Send + Sync + 'static) {
+        self.on_missing = Some(Callback(Arc::new(on_missing)));
+    }
+
+    /// Returns the regex that captures all the variables in the template, a
+    /// variable can span multiple lines.
+    /// The delimiters are part of the pattern so they must not swallow the
+    /// capture group, i.e. `[` & `]` would form a character class.
+    fn token_regex(option: &TemplateNestOption) -> Result<Regex, TemplateNestError> {
+        let token_regex = Regex::new(&token_pattern(
+            &option.delimiters.0,
+            &option.delimiters.1,
+            &option.token_escape_char,
+        ))
+        .map_err(|err| TemplateNestError::InvalidDelimiters(err.to_string()))?;
+
+        match token_regex.captures_len() {
+            2 => Ok(token_regex),
+            _ => Err(TemplateNestError::InvalidDelimiters(
+                "pattern must have exactly one capture group for the variable name".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the regex that captures the end delimiter of an escaped end
+    /// delimiter, it's used to remove the escape char. None if
+    /// token_escape_char is not set.
+    fn escaped_end_regex(option: &TemplateNestOption) -> Result<Option<Regex>, TemplateNestError> {
+        if option.token_escape_char.is_empty() {
+            return Ok(None);
+        }
+        Regex::new(&escaped_end_pattern(
+            &option.delimiters.1,
+            &option.token_escape_char,
+        ))
+        .map(Some)
+        .map_err(|err| TemplateNestError::InvalidDelimiters(err.to_string()))
+    }
+
+    /// Rebuilds the cache by walking the template directories again, templates
+    /// that were deleted are dropped from the cache.
+    pub fn reload(&mut self) -> Result<(), TemplateNestError> {
+        let cache = Self::build_cache(
+            &self.option,
+            &self.token_regex,
+            self.escaped_end_regex.as_ref(),
+        )?;
+        *self.cache.get_mut().unwrap() = cache;
+        Ok(())
+    }
+
+    fn build_cache(
+        option: &TemplateNestOption,
+        token_regex: &Regex,
+        escaped_end_regex: Option<&Regex>,
+    ) -> Result<HashMap<String, Arc<TemplateFileIndex>>, TemplateNestError> {
+        // In lazy mode templates are indexed when they're first rendered.
+        match option.lazy {
+            true => Ok(HashMap::new()),
+            false => Self::index_templates(
+                option,
+                token_regex,
+                escaped_end_regex,
+                Self::template_files(option),
+            ),
+        }
+    }
+
+    /// Walks the template directories and returns the template name & path of
+    /// every template file. A template in a later directory overrides the one
+    /// in an earlier directory.
+    fn template_files(option: &TemplateNestOption) -> Vec<(String, PathBuf)> {
+        let mut files = HashMap::new();
+        for directory in option.template_dirs() {
+            Self::walk_template_dir(option, directory, &mut files);
+        }
+        files.into_iter().collect()
+    }
+
+    fn walk_template_dir(
+        option: &TemplateNestOption,
+        directory: &Path,
+        files: &mut HashMap<String, PathBuf>,
+    ) {
+        // Symlink loops are detected by walkdir & returned as errors, they're
+        // skipped like any other error.
+        let mut walker = WalkDir::new(directory).follow_links(option.follow_symlinks);
+        if let Some(max_walk_depth) = option.max_walk_depth {
+            walker = walker.max_depth(max_walk_depth);
+        }
+        for entry in walker
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| match e.metadata() {
+                Ok(m) => {
+                    // entry must be a file and the file name must end with option.extension
+                    m.is_file()
+                        && e.file_name().to_string_lossy().ends_with(&option.extension)
+                        && option
+                            .file_filter
+                            .as_ref()
+                            .map_or(true, |file_filter| file_filter(e.path()))
+                }
+                Err(_) => false,
+            })
+        {
+            let file_name = entry
+                .path()
+                .strip_prefix(directory)
+                .unwrap()
+                .to_string_lossy();
+
+            let file_name = if option.extension.is_empty() {
+                &file_name
+            } else {
+                file_name
+                    .strip_suffix(&format!(".{}", &option.extension))
+                    .unwrap()
+            };
+
+            // Template names always use '/' as the separator regardless of the
+            // platform, this is how they're referenced in the template hash.
+            let file_name = file_name.replace(MAIN_SEPARATOR, "/");
+            files.insert(file_name, entry.into_path());
+        }
+    }
+
+    /// Indexes the given template files, the files are indexed in parallel if
+    /// `rayon` feature is enabled.
+    fn index_templates(
+        option: &TemplateNestOption,
+        token_regex: &Regex,
+        escaped_end_regex: Option<&Regex>,
+        files: Vec<(String, PathBuf)>,
+    ) -> Result<HashMap<String, Arc<TemplateFileIndex>>, TemplateNestError> {
+        #[cfg(feature = "rayon")]
+        let files = files.into_par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let files = files.into_iter();
+
+        files
+            .map(|(name, path)| {
+                let index = Self::index(option, token_regex, escaped_end_regex, &path)?;
+                Ok((name, Arc::new(index)))
+            })
+            .collect()
+    }
+
+    fn template_name_to_file(option: &TemplateNestOption, template_name: &str) -> PathBuf {
+        let file_name = if option.extension.is_empty() {
+            template_name.to_string()
+        } else {
+            format!("{}.{}", template_name, option.extension)
+        };
+
+        // Template names use '/' as the separator, convert it to platform
+        // specific path. Directories are searched from the last to the first,
+        // if the file doesn't exist then the path in `directory` is returned.
+        let to_file = |directory: &PathBuf| {
+            let mut file = directory.clone();
+            file.extend(file_name.split('/'));
+            file
+        };
+        option
+            .template_dirs()
+            .rev()
+            .map(to_file)
+            .find(|file| file.is_file())
+            .unwrap_or_else(|| to_file(&option.directory))
+    }
+
+    /// Given a template name, returns the "index" of the template file, it
+    /// contains the contents of the file and all the variables that are
+    /// present.
+    fn index(
+        option: &TemplateNestOption,
+        token_regex: &Regex,
+        escaped_end_regex: Option<&Regex>,
+        template_file: &Path,
+    ) -> Result<TemplateFileIndex, TemplateNestError> {
+        if !template_file.is_file() {
+            return Err(TemplateNestError::TemplateFileNotFound(
+                template_file.display().to_string(),
+            ));
+        }
+
+        let contents = match option.lossy_utf8 {
+            true => {
+                fs::read(template_file).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            }
+            false => fs::read_to_string(template_file),
+        };
+        let contents = match contents {
+            Ok(file_contents) => file_contents,
+            Err(err) => {
+                return Err(TemplateNestError::TemplateFileReadError {
+                    path: template_file.display().to_string(),
+                    source: err,
+                });
+            }
+        };
+
+        // A leading BOM is not part of the template text.
+        let mut file_offset = 0;
+        let mut contents = match contents.strip_prefix('\u{feff}') {
+            Some(contents) => {
+                file_offset = '\u{feff}'.len_utf8();
+                contents.to_string()
+            }
+            None => contents,
+        };
+
+        // The newline at the end of the file is not part of the template, it's
+        // dropped so that nested templates don't add a newline where they're
+        // embedded. The rest of the trailing whitespace is kept.
+        if option.trim_trailing {
+            let len = contents.len();
+            if contents.ends_with("\r\n") {
+                contents.truncate(len - 2);
+            } else if contents.ends_with('\n') {
+                contents.truncate(len - 1);
+            }
+        }
+
+        // If the first line is a delimiters directive then the delimiters are
+        // used for this file only, the directive is removed from the contents.
+        // Variables are rendered from their positions so the delimiters are
+        // only needed here.
+        let file_token_regex;
+        let file_escaped_end_regex;
+        let (token_regex, escaped_end_regex) = match delimiters_directive(option, &contents) {
+            Some((delimiters, directive_len)) => {
+                contents.replace_range(..directive_len, "");
+                file_offset += directive_len;
+                let end_pattern = regex::escape(&delimiters.1);
+                file_token_regex = Regex::new(&token_pattern(
+                    &regex::escape(&delimiters.0),
+                    &end_pattern,
+                    &option.token_escape_char,
+                ))
+                .unwrap();
+                file_escaped_end_regex = escaped_end_regex.map(|_| {
+                    Regex::new(&escaped_end_pattern(
+                        &end_pattern,
+                        &option.token_escape_char,
+                    ))
+                    .unwrap()
+                });
+                (&file_token_regex, file_escaped_end_regex.as_ref())
+            }
+            None => (token_regex, escaped_end_regex),
+        };
+
+        let mut variable_names = HashSet::new();
+        let mut variables: Vec<TemplateFileVariable> = vec![];
+        // Indexes of the If & Block tokens whose EndIf & EndBlock tokens
+        // haven't been found yet. They share the stack so that the sections
+        // nest & never overlap.
+        let mut sections: Vec<usize> = vec![];
+        for cap in token_regex.captures_iter(&contents) {
+            let whole_capture = cap.get(0).unwrap();
+            let start_position = whole_capture.start();
+
+            // An escaped end delimiter outside of a token has no capture, only
+            // the escape char is removed.
+            if cap.get(1).is_none() {
+                variables.push(TemplateFileVariable {
+                    indent: "".to_string(),
+                    name: "".to_string(),
+                    kind: TokenKind::Escaped,
+                    raw: false,
+                    filters: vec![],
+                    start_position,
+                    end_position: start_position + option.token_escape_char.len(),
+                });
+                continue;
+            }
+
+            // If token_escape_char is set then look behind for it and if we
+            // find the escape char then we're only going to remove the escape
+            // char and not remove this variable.
+            //
+            // The variable can be at the beginning of the file, that will mean
+            // calculating escape_char_start results in an overflow. The
+            // escape_char_start might also not be on a char boundary if the
+            // preceding character is multi-byte, in that case it's not escaped.
+            if !option.token_escape_char.is_empty()
+                && start_position >= option.token_escape_char.len()
+            {
+                let escape_char_start = start_position - option.token_escape_char.len();
+                if contents.get(escape_char_start..start_position)
+                    == Some(option.token_escape_char.as_str())
+                {
+                    variables.push(TemplateFileVariable {
+                        indent: "".to_string(),
+                        name: "".to_string(),
+                        kind: TokenKind::Escaped,
+                        raw: false,
+                        filters: vec![],
+                        start_position: escape_char_start,
+                        end_position: escape_char_start + option.token_escape_char.len(),
+                    });
+                    continue;
+                }
+            }
+
+            // If comment_markers are set then a token wrapped in the markers is
+            // a comment, it's removed from the output.
+            if let Some((prefix, suffix)) = &option.comment_markers {
+                let token = &cap[1];
+                if token.len() >= prefix.len() + suffix.len()
+                    && token.starts_with(prefix.as_str())
+                    && token.ends_with(suffix.as_str())
+                {
+                    variables.push(TemplateFileVariable {
+                        indent: "".to_string(),
+                        name: "".to_string(),
+                        kind: TokenKind::Comment,
+                        raw: false,
+                        filters: vec![],
+                        start_position,
+                        end_position: whole_capture.end(),
+                    });
+                    continue;
+                }
+            }
+
+            // If fixed_indent is enable then record the indent for this
+            // variable. To get the indent we look at each character in reverse
+            // from the start position of the variable until we find a newline
+            // character.
+            let indent = match option.fixed_indent {
+                true => {
+                    // If we do not encounter a newline then that means this
+                    // variable is on the first line, the indent starts at the
+                    // beginning of the file.
+                    //
+                    // With CRLF line endings the '\r' precedes the '\n' so it's
+                    // never counted in the indent. Characters are counted, not
+                    // bytes, so multi-byte characters are a single space.
+                    let line_start = contents[..start_position].rfind('\n').map_or(0, |p| p + 1);
+                    contents[line_start..start_position]
+                        .chars()
+                        .map(|c| if c == '\t' { '\t' } else { ' ' })
+                        .collect()
+                }
+                false => "".to_string(),
+            };
+
+            // The escape char is removed from the escaped end delimiters in
+            // the token.
+            let unescaped = match escaped_end_regex {
+                Some(escaped_end_regex) => escaped_end_regex.replace_all(&cap[1], "${1}"),
+                None => Cow::Borrowed(&cap[1]),
+            };
+            let mut variable_name = unescaped.as_ref();
+            let mut start_position = start_position;
+            let mut end_position = whole_capture.end();
+
+            // If trim_marker is set then a token with the marker right after
+            // the start delimiter trims the whitespace before it, and a token
+            // with the marker right before the end delimiter trims the
+            // whitespace after it. The whitespace is made part of the token so
+            // that it's removed along with the token.
+            if !option.trim_marker.is_empty() {
+                if let Some(name) = variable_name.strip_prefix(&option.trim_marker) {
+                    variable_name = name;
+                    let prev_end = variables.last().map_or(0, |v| v.end_position);
+                    start_position = prev_end + contents[prev_end..start_position].trim_end().len();
+                }
+                if let Some(name) = variable_name.strip_suffix(&option.trim_marker) {
+                    variable_name = name;
+                    let after = &contents[end_position..];
+                    end_position += after.len() - after.trim_start().len();
+                }
+            }
+
+            let mut variable_name = variable_name.trim();
+            let mut kind = TokenKind::Variable;
+
+            // If conditionals are enabled then match the if:name & endif:name
+            // tokens, they are paired using a stack so that sections can be
+            // nested.
+            if option.conditionals {
+                if let Some(name) = variable_name.strip_prefix("if:") {
+                    variable_name = name.trim();
+                    sections.push(variables.len());
+                    kind = TokenKind::If(0);
+                } else if let Some(name) = variable_name.strip_prefix("endif:") {
+                    variable_name = name.trim();
+                    let if_idx = match sections.pop() {
+                        Some(idx)
+                            if matches!(variables[idx].kind, TokenKind::If(_))
+                                && variables[idx].name == variable_name =>
+                        {
+                            idx
+                        }
+                        // A block that's opened in the conditional must end in
+                        // it.
+                        Some(idx) if matches!(variables[idx].kind, TokenKind::Block(_)) => {
+                            return Err(TemplateNestError::UnmatchedBlock(
+                                variables[idx].name.to_string(),
+                            ))
+                        }
+                        _ => {
+                            return Err(TemplateNestError::UnmatchedConditional(
+                                variable_name.to_string(),
+                            ))
+                        }
+                    };
+                    variables[if_idx].kind = TokenKind::If(variables.len());
+                    kind = TokenKind::EndIf(if_idx);
+                }
+            }
+
+            // If blocks are enabled then match the block name & endblock
+            // tokens, they're paired like the conditionals. Block names are
+            // not variables so they're not added to variable_names.
+            if option.blocks {
+                if let Some(name) = variable_name.strip_prefix("block ") {
+                    sections.push(variables.len());
+                    variables.push(TemplateFileVariable {
+                        indent,
+                        start_position,
+                        end_position,
+                        name: name.trim().to_string(),
+                        kind: TokenKind::Block(0),
+                        raw: false,
+                        filters: vec![],
+                    });
+                    continue;
+                } else if variable_name == "endblock" {
+                    let block_idx = match sections.pop() {
+                        Some(idx) if matches!(variables[idx].kind, TokenKind::Block(_)) => idx,
+                        // A conditional that's opened in the block must end in
+                        // it.
+                        Some(idx) => {
+                            return Err(TemplateNestError::UnmatchedConditional(
+                                variables[idx].name.to_string(),
+                            ))
+                        }
+                        None => {
+                            return Err(TemplateNestError::UnmatchedBlock("endblock".to_string()))
+                        }
+                    };
+                    variables[block_idx].kind = TokenKind::Block(variables.len());
+                    variables.push(TemplateFileVariable {
+                        indent,
+                        start_position,
+                        end_position,
+                        name: variables[block_idx].name.to_string(),
+                        kind: TokenKind::EndBlock(block_idx),
+                        raw: false,
+                        filters: vec![],
+                    });
+                    continue;
+                }
+            }
+
+            // If classes are enabled then `classes name1 name2 ...` merges the
+            // classes in the variables, the names are all variables.
+            if option.classes {
+                if let Some(names) = variable_name.strip_prefix("classes ") {
+                    let names: Vec<String> = names.split_whitespace().map(String::from).collect();
+                    variable_names.extend(names.iter().cloned());
+                    variables.push(TemplateFileVariable {
+                        indent,
+                        start_position,
+                        end_position,
+                        name: "classes".to_string(),
+                        kind: TokenKind::Classes(names),
+                        raw: false,
+                        filters: vec![],
+                    });
+                    continue;
+                }
+            }
+
+            // If each loops are enabled then `each name with template` loops
+            // over the object `name`, it's a variable like any other.
+            if option.each_loops {
+                if let Some(each) = variable_name.strip_prefix("each ") {
+                    let (name, template) = each
+                        .split_once(" with ")
+                        .map(|(name, template)| (name.trim(), template.trim()))
+                        .filter(|(name, template)| !name.is_empty() && !template.is_empty())
+                        .ok_or_else(|| {
+                            TemplateNestError::InvalidEachLoop(variable_name.to_string())
+                        })?;
+                    variable_name = name;
+                    kind = TokenKind::Each(template.to_string());
+                }
+            }
+
+            // If filters are enabled then the filters follow the variable name,
+            // separated by `|`.
+            let mut filters = vec![];
+            if option.filters && kind == TokenKind::Variable {
+                if let Some((name, chain)) = variable_name.split_once('|') {
+                    variable_name = name.trim();
+                    for filter in chain.split('|').map(str::trim) {
+                        filters.push(
+                            Escaper::from_filter(filter).ok_or_else(|| {
+                                TemplateNestError::UnknownFilter(filter.to_string())
+                            })?,
+                        );
+                    }
+                }
+            }
+
+            // If the variable is prefixed with raw_prefix then it's not
+            // escaped.
+            let mut raw = false;
+            if !option.raw_prefix.is_empty() && kind == TokenKind::Variable {
+                if let Some(name) = variable_name.strip_prefix(&option.raw_prefix) {
+                    variable_name = name.trim();
+                    raw = true;
+                }
+            }
+
+            if let Some(pattern) = &option.variable_name_pattern {
+                if !pattern.is_match(variable_name) {
+                    return Err(TemplateNestError::InvalidVariableName(
+                        variable_name.to_string(),
+                    ));
+                }
+            }
+
+            variable_names.insert(variable_name.to_string());
+            variables.push(TemplateFileVariable {
+                indent,
+                start_position,
+                end_position,
+                name: variable_name.to_string(),
+                kind,
+                raw,
+                filters,
+            });
+        }
+
+        if let Some(idx) = sections.pop() {
+            let name = variables[idx].name.to_string();
+            return Err(match variables[idx].kind {
+                TokenKind::Block(_) => TemplateNestError::UnmatchedBlock(name),
+                _ => TemplateNestError::UnmatchedConditional(name),
+            });
+        }
+
+        let last_modified = template_file.metadata().unwrap().modified().unwrap();
+        let file_index = TemplateFileIndex {
+            variable_names,
+            contents: contents.into(),
+            variables,
+            last_modified,
+            file_offset,
+        };
+        Ok(file_index)
+    }
+
+    /// Returns the names of all the templates, sorted. In lazy mode the
+    /// directory is walked as the cache only has the rendered templates.
+    pub fn list_templates(&self) -> Vec<String> {
+        let mut names: Vec<String> = match self.option.lazy {
+            true => Self::template_files(&self.option)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect(),
+            false => self.cache.read().unwrap().keys().cloned().collect(),
+        };
+        names.sort();
+        names
+    }
+
+    /// Returns statistics of the templates in cache.
+    pub fn cache_stats(&self) -> CacheStats {
+        let cache = self.cache.read().unwrap();
+        CacheStats {
+            templates: cache.len(),
+            bytes: cache.values().map(|index| index.contents.len()).sum(),
+            variables: cache
+                .values()
+                .map(|index| {
+                    index
+                        .variables
+                        .iter()
+                        .filter(|var| var.kind == TokenKind::Variable)
+                        .count()
+                })
+                .sum(),
+        }
+    }
+
+    /// Returns true if the template exists. In lazy mode the template file is
+    /// looked up if it's not in the cache.
+    pub fn contains_template(&self, template_name: &str) -> bool {
+        self.cache.read().unwrap().contains_key(template_name)
+            || (self.option.lazy
+                && is_contained(template_name)
+                && Self::template_name_to_file(&self.option, template_name).is_file())
+    }
+
+    /// Returns the names of the templates in the cache along with their
+    /// variable names, both sorted. In lazy mode only the templates that have
+    /// been indexed are returned.
+    pub fn templates_with_variables(&self) -> Vec<(String, Vec<String>)> {
+        let cache = self.cache.read().unwrap();
+        let mut templates: Vec<(String, Vec<String>)> = cache
+            .iter()
+            .map(|(name, index)| {
+                let mut variables: Vec<String> = index.variable_names.iter().cloned().collect();
+                variables.sort();
+                (name.to_string(), variables)
+            })
+            .collect();
+        templates.sort();
+        templates
+    }
+
+    /// Returns the tokens of the named template in order.
+    pub fn template_tokens(&self, name: &str) -> Result<Vec<TemplateToken>, TemplateNestError> {
+        let t_index = self.template_index(name)?;
+        let tokens = t_index
+            .variables
+            .iter()
+            .map(|var| TemplateToken {
+                name: var.name.to_string(),
+                start: t_index.file_offset + var.start_position,
+                end: t_index.file_offset + var.end_position,
+                kind: match var.kind {
+                    TokenKind::Variable => TemplateTokenKind::Variable,
+                    TokenKind::Escaped => TemplateTokenKind::Escaped,
+                    TokenKind::Comment => TemplateTokenKind::Comment,
+                    TokenKind::If(_) => TemplateTokenKind::If,
+                    TokenKind::EndIf(_) => TemplateTokenKind::EndIf,
+                    TokenKind::Block(_) => TemplateTokenKind::Block,
+                    TokenKind::EndBlock(_) => TemplateTokenKind::EndBlock,
+                    TokenKind::Each(_) => TemplateTokenKind::Each,
+                    TokenKind::Classes(_) => TemplateTokenKind::Classes,
+                },
+            })
+            .collect();
+        Ok(tokens)
+    }
+
+    /// Removes the named template from the cache, it's indexed again when it's
+    /// rendered next. Returns true if the template was in the cache.
+    pub fn invalidate(&self, name: &str) -> bool {
+        self.cache.write().unwrap().remove(name).is_some()
+    }
+
+    /// Indexes & caches the named templates without rendering them, templates
+    /// that are already cached are only re-indexed if they have been modified.
+    pub fn warm(&self, names: &[&str]) -> Result<(), TemplateNestError> {
+        for name in names {
+            self.template_index(name)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the template from cache, the template is indexed
+    /// and cached if it's not in the cache or has been modified since.
+    fn template_index(
+        &self,
+        template_name: &str,
+    ) -> Result<Arc<TemplateFileIndex>, TemplateNestError> {
+        let (index, cached) = self.find_template_index(template_name)?;
+        if !cached {
+            self.cache
+                .write()
+                .unwrap()
+                .insert(template_name.to_string(), Arc::clone(&index));
+        }
+        Ok(index)
+    }
+
+    /// Returns the index of the template & true if it's the one in cache, the
+    /// template is indexed if it's not cached or has been modified. The cache
+    /// is not changed.
+    fn find_template_index(
+        &self,
+        template_name: &str,
+    ) -> Result<(Arc<TemplateFileIndex>, bool), TemplateNestError> {
+        if !is_contained(template_name) {
+            return Err(TemplateNestError::PathTraversal(template_name.to_string()));
+        }
+        let t_file = Self::template_name_to_file(&self.option, template_name);
+
+        let cached = self.cache.read().unwrap().get(template_name).cloned();
+        if let Some(index) = cached {
+            // If the file has been modified then get the latest index.
+            let last_modified = t_file.metadata().and_then(|m| m.modified());
+            if matches!(last_modified, Ok(m) if m <= index.last_modified) {
+                return Ok((index, true));
+            }
+        }
+
+        let index = Self::index(
+            &self.option,
+            &self.token_regex,
+            self.escaped_end_regex.as_ref(),
+            t_file.as_path(),
+        )?;
+        Ok((Arc::new(index), false))
+    }
+
+    /// Given a TemplateHash, it parses the TemplateHash and renders a String
+    /// output.
+    pub fn render(&self, to_render: &Value) -> Result<String, TemplateNestError> {
+        let rendered = self.render_nested(to_render, &mut RenderContext::default())?;
+        Ok(self.finish(rendered))
+    }
+
+    /// Renders the TemplateHash & appends the output to out, the buffer can be
+    /// cleared & reused between renders to avoid allocating. If rendering
+    /// fails then out is left as it was.
+    pub fn render_into(
+        &self,
+        to_render: &Value,
+        out: &mut String,
+    ) -> Result<(), TemplateNestError> {
+        let start = out.len();
+        if let Err(err) = self.render_nested_into(to_render, &mut RenderContext::default(), out) {
+            out.truncate(start);
+            return Err(err);
+        }
+
+        // The output is only moved out of the buffer if it has to be
+        // post-processed as a whole.
+        if self.option.collapse_blank_lines || self.post_render.is_some() {
+            let rendered = out.split_off(start);
+            out.push_str(&self.finish(rendered));
+        } else {
+            self.trim(out, start);
+        }
+        Ok(())
+    }
+
+    /// Renders the TemplateHash & writes the output to path atomically, it's
+    /// written to a temporary file in the same directory that is then renamed
+    /// to path. The file at path is either replaced completely or not at all.
+    pub fn render_to_file(
+        &self,
+        to_render: &Value,
+        path: impl AsRef<Path>,
+    ) -> Result<(), TemplateNestError> {
+        // Distinguishes the temporary files of concurrent renders.
+        static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = path.as_ref();
+        let rendered = self.render(to_render)?;
+        let write_error = |source| TemplateNestError::WriteError {
+            path: path.display().to_string(),
+            source,
+        };
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| write_error(io::ErrorKind::InvalidInput.into()))?;
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_name);
+
+        let written = fs::write(&temp_path, rendered).and_then(|_| fs::rename(&temp_path, path));
+        if let Err(err) = written {
+            let _ = fs::remove_file(&temp_path);
+            return Err(write_error(err));
+        }
+        Ok(())
+    }
+
+    /// Renders the TemplateHash & reindents the output, every line is
+    /// indented by two spaces per open HTML element. The contents of `<pre>`
+    /// & `<textarea>` elements are not changed.
+    pub fn render_pretty(&self, to_render: &Value) -> Result<String, TemplateNestError> {
+        let mut rendered = pretty::reindent(&self.render(to_render)?);
+        if self.option.trim_trailing {
+            rendered.truncate(rendered.trim_end().len());
+        }
+        Ok(rendered)
+    }
+
+    /// Renders every TemplateHash in items, returns the first error if any of
+    /// them fails. With the rayon feature the items are rendered in parallel.
+    pub fn render_all(&self, items: &[Value]) -> Result<Vec<String>, TemplateNestError> {
+        #[cfg(feature = "rayon")]
+        let items = items.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let items = items.iter();
+
+        items.map(|to_render| self.render(to_render)).collect()
+    }
+
+    /// Renders the TemplateHash using the given name label instead of the one
+    /// set in the options.
+    pub fn render_with_label(
+        &self,
+        to_render: &Value,
+        label: &str,
+    ) -> Result<String, TemplateNestError> {
+        let mut ctx = RenderContext {
+            label: Some(label.to_string()),
+            ..Default::default()
+        };
+        let rendered = self.render_nested(to_render, &mut ctx)?;
+        Ok(self.finish(rendered))
+    }
+
+    /// Serializes data to a TemplateHash and renders it, data can be any type
+    /// that implements Serialize.
+    pub fn render_value<T: Serialize>(&self, data: &T) -> Result<String, TemplateNestError> {
+        let to_render = serde_json::to_value(data)
+            .map_err(|err| TemplateNestError::Serialize(err.to_string()))?;
+        self.render(&to_render)
+    }
+
+    /// Parses the YAML document to a TemplateHash and renders it.
+    #[cfg(feature = "yaml")]
+    pub fn render_yaml(&self, yaml: &str) -> Result<String, TemplateNestError> {
+        let to_render: Value = serde_yaml::from_str(yaml)
+            .map_err(|err| TemplateNestError::InvalidYaml(err.to_string()))?;
+        self.render(&to_render)
+    }
+
+    /// Parses the TOML document to a TemplateHash and renders it, tables are
+    /// objects & arrays of tables are arrays of template hashes.
+    #[cfg(feature = "toml")]
+    pub fn render_toml(&self, toml: &str) -> Result<String, TemplateNestError> {
+        let to_render: Value =
+            toml::from_str(toml).map_err(|err| TemplateNestError::InvalidToml(err.to_string()))?;
+        self.render(&to_render)
+    }
+
+    /// Renders the named template with the variables in data, data doesn't
+    /// need to contain the name label.
+    pub fn render_named(&self, name: &str, data: &Value) -> Result<String, TemplateNestError> {
+        let empty = Map::new();
+        let t_hash = match data {
+            Value::Object(t_hash) => t_hash,
+            Value::Null => &empty,
+            _ => return Err(TemplateNestError::InvalidTemplateHash(data.to_string())),
+        };
+        let rendered = self.render_template(name, t_hash, &mut RenderContext::default())?;
+        Ok(self.finish(rendered))
+    }
+
+    /// Renders only the contents of the named block of the template with the
+    /// variables in data, `blocks` must be enabled.
+    pub fn render_fragment(
+        &self,
+        template: &str,
+        fragment: &str,
+        data: &Value,
+    ) -> Result<String, TemplateNestError> {
+        let empty = Map::new();
+        let t_hash = match data {
+            Value::Object(t_hash) => t_hash,
+            Value::Null => &empty,
+            _ => return Err(TemplateNestError::InvalidTemplateHash(data.to_string())),
+        };
+
+        let t_index = self.template_index(template)?;
+        let tokens = t_index
+            .variables
+            .iter()
+            .enumerate()
+            .find_map(|(idx, var)| match var.kind {
+                TokenKind::Block(endblock_idx) if var.name == fragment => {
+                    Some(idx + 1..endblock_idx)
+                }
+                _ => None,
+            })
+            .ok_or_else(|| TemplateNestError::FragmentNotFound {
+                template: template.to_string(),
+                fragment: fragment.to_string(),
+            })?;
+
+        let mut ctx = RenderContext {
+            templates: vec![template.to_string()],
+            ..Default::default()
+        };
+        let mut rendered = String::new();
+        self.render_tokens(template, t_hash, &t_index, tokens, &mut rendered, &mut ctx)?;
+        Ok(self.finish(rendered))
+    }
+
+    /// Renders the named template with the string variables in vars.
+    pub fn render_map(
+        &self,
+        template: &str,
+        vars: &HashMap<String, String>,
+    ) -> Result<String, TemplateNestError> {
+        let t_hash = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+            .collect::<Map<String, Value>>();
+        let rendered = self.render_template(template, &t_hash, &mut RenderContext::default())?;
+        Ok(self.finish(rendered))
+    }
+
+    /// Renders the TemplateHash and returns the output along with the sorted
+    /// names of all the templates that were rendered.
+    pub fn render_with_deps(
+        &self,
+        to_render: &Value,
+    ) -> Result<(String, Vec<String>), TemplateNestError> {
+        let mut ctx = RenderContext {
+            deps: Some(BTreeSet::new()),
+            ..Default::default()
+        };
+        let rendered = self.render_nested(to_render, &mut ctx)?;
+        let deps = ctx.deps.unwrap_or_default().into_iter().collect();
+        Ok((self.finish(rendered), deps))
+    }
+
+    /// Checks the TemplateHash without rendering it, all the errors are
+    /// returned instead of stopping at the first one. Every Object that is
+    /// rendered must have a valid name label and the template must exist, keys
+    /// that are not variables of the template are only checked if
+    /// `die_on_bad_params` is set. Missing variables are checked if
+    /// `die_on_unused_variables` is set. Like render, the hash returned by the
+    /// pre_render callback is checked. Templates are not added to the cache.
+    pub fn validate(&self, to_render: &Value) -> Result<(), Vec<TemplateNestError>> {
+        let mut errors = vec![];
+        self.validate_nested(to_render, &mut RenderContext::default(), &mut errors);
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Returns true if the TemplateHash passes validate, nothing is rendered
+    /// & the cache is not changed. Only the pre_render callback is called.
+    pub fn can_render(&self, to_render: &Value) -> bool {
+        self.validate(to_render).is_ok()
+    }
+
+    fn validate_nested(
+        &self,
+        to_validate: &Value,
+        ctx: &mut RenderContext,
+        errors: &mut Vec<TemplateNestError>,
+    ) {
+        match to_validate {
+            Value::Array(_) => {}
+            Value::Object(t_hash) if self.raw_value(t_hash).is_none() => {}
+            _ => return,
+        }
+
+        if ctx.depth >= self.option.max_depth {
+            errors.push(TemplateNestError::MaxDepthExceeded(self.option.max_depth));
+            return;
+        }
+
+        ctx.depth += 1;
+        match to_validate {
+            Value::Array(t_array) => {
+                for (idx, t) in t_array.iter().enumerate() {
+                    ctx.path.push(format!("[{idx}]"));
+                    self.validate_nested(t, ctx, errors);
+                    ctx.path.pop();
+                }
+            }
+            Value::Object(t_hash) => match self.template_name(t_hash, ctx) {
+                Ok(t_path) => self.validate_template(t_path, t_hash, ctx, errors),
+                Err(err) => errors.push(err),
+            },
+            _ => unreachable!(),
+        }
+        ctx.depth -= 1;
+    }
+
+    /// Checks the template hash like render_template_into renders it, the
+    /// errors are added to errors. Only the values of the variables that are
+    /// rendered are checked.
+    fn validate_template(
+        &self,
+        t_path: &str,
+        t_hash: &Map<String, Value>,
+        ctx: &mut RenderContext,
+        errors: &mut Vec<TemplateNestError>,
+    ) {
+        if let Err(err) = self.check_circular_reference(t_path, ctx) {
+            errors.push(err);
+            return;
+        }
+
+        // pre_render may add or change keys, the hash it returns is the one
+        // that's rendered.
+        let t_hash = match &self.pre_render {
+            Some(pre_render) => {
+                let mut t_hash = t_hash.clone();
+                pre_render(t_path, &mut t_hash);
+                Cow::Owned(t_hash)
+            }
+            None => Cow::Borrowed(t_hash),
+        };
+        let t_hash = t_hash.as_ref();
+
+        let t_index = match self.find_template_index(t_path) {
+            Err(TemplateNestError::TemplateFileNotFound(missing)) => {
+                match self.fallback(t_path) {
+                    Some(fallback) => {
+                        let mut fallback_hash = Map::new();
+                        fallback_hash.insert(MISSING_NAME_LABEL.to_string(), t_path.into());
+                        self.validate_template(fallback, &fallback_hash, ctx, errors);
+                    }
+                    None if self.option.missing_template_is_empty => {}
+                    None => errors.push(TemplateNestError::TemplateFileNotFound(missing)),
+                }
+                return;
+            }
+            Err(err) => {
+                errors.push(err);
+                return;
+            }
+            Ok((t_index, _)) => t_index,
+        };
+        // Only the first error of the template is reported.
+        let bad_params = self.check_bad_params(&t_index, t_hash, ctx).err();
+
+        // Walks the tokens like render_tokens, variables inside a false
+        // conditional section are not required & not rendered.
+        let mut children = vec![];
+        let mut visited = HashSet::new();
+        let mut missing = None;
+        let mut idx = 0;
+        while idx < t_index.variables.len() {
+            let var = &t_index.variables[idx];
+            idx += 1;
+            match var.kind {
+                TokenKind::If(endif_idx) => {
+                    if !is_truthy(self.lookup(t_path, t_hash, &var.name)) {
+                        idx = endif_idx + 1;
+                    }
+                }
+                TokenKind::Variable => match self.lookup(t_path, t_hash, &var.name) {
+                    Some(value) => {
+                        if visited.insert(&var.name) {
+                            children.push((format!(".{}", var.name), Cow::Borrowed(value)));
+                        }
+                    }
+                    None => {
+                        missing.get_or_insert(&var.name);
+                    }
+                },
+                // The entries of the loop are rendered with the loop template.
+                TokenKind::Each(ref template) => {
+                    match self.each_value(t_path, t_hash, var, template, ctx) {
+                        Ok(Some(entries)) => {
+                            children.push((format!(".{}", var.name), Cow::Owned(entries)))
+                        }
+                        Ok(None) => {
+                            missing.get_or_insert(&var.name);
+                        }
+                        Err(err) => errors.push(err),
+                    }
+                }
+                // An overridden block renders the override in place of its
+                // contents.
+                TokenKind::Block(endblock_idx) => {
+                    if let Some(value) = self.block_override(t_hash, &var.name) {
+                        let segment = format!(".{}.{}", BLOCKS_LABEL, var.name);
+                        children.push((segment, Cow::Borrowed(value)));
+                        idx = endblock_idx + 1;
+                    }
+                }
+                TokenKind::Escaped
+                | TokenKind::Comment
+                | TokenKind::EndIf(_)
+                | TokenKind::EndBlock(_)
+                | TokenKind::Classes(_) => {}
+            }
+        }
+        if let Some(err) = bad_params {
+            errors.push(err);
+        } else if let Some(var_name) = missing.filter(|_| self.option.die_on_unused_variables) {
+            errors.push(TemplateNestError::MissingVariable(var_name.to_string()));
+        }
+
+        ctx.templates.push(t_path.to_string());
+        for (segment, child) in children {
+            ctx.path.push(segment);
+            self.validate_nested(&child, ctx, errors);
+            ctx.path.pop();
+        }
+        ctx.templates.pop();
+    }
+
+    /// Returns the string if t_hash only contains the raw_key with a string.
+    fn raw_value<'a>(&self, t_hash: &'a Map<String, Value>) -> Option<&'a str> {
+        match t_hash.len() == 1 && !self.option.raw_key.is_empty() {
+            true => t_hash.get(&self.option.raw_key)?.as_str(),
+            false => None,
+        }
+    }
+
+    /// Post-processes the output of the outermost render.
+    fn finish(&self, mut rendered: String) -> String {
+        self.trim(&mut rendered, 0);
+
+        let rendered = match self.option.collapse_blank_lines {
+            true => collapse_blank_lines(&rendered),
+            false => rendered,
+        };
+        match &self.post_render {
+            Some(post_render) => post_render(rendered),
+            None => rendered,
+        }
+    }
+
+    /// Applies trim_trailing & trim_leading to the output in rendered that
+    /// starts at start, without cloning it.
+    fn trim(&self, rendered: &mut String, start: usize) {
+        if self.option.trim_trailing {
+            let len_withoutcrlf = rendered[start..].trim_end().len();
+            rendered.truncate(start + len_withoutcrlf);
+        }
+        if self.option.trim_leading {
+            let leading = rendered[start..].len() - rendered[start..].trim_start().len();
+            rendered.replace_range(start..start + leading, "");
+        }
+    }
+
+    fn render_nested(
+        &self,
+        to_render: &Value,
+        ctx: &mut RenderContext,
+    ) -> Result<String, TemplateNestError> {
+        let mut rendered = String::new();
+        self.render_nested_into(to_render, ctx, &mut rendered)?;
+        Ok(rendered)
+    }
+
+    /// Renders to_render & appends the output to rendered.
+    fn render_nested_into(
+        &self,
+        to_render: &Value,
+        ctx: &mut RenderContext,
+        rendered: &mut String,
+    ) -> Result<(), TemplateNestError> {
+        match to_render {
+            Value::Null => return Ok(()),
+            Value::Bool(x) => {
+                if self.option.bool_as_text {
+                    rendered.push_str(if *x { "true" } else { "false" });
+                }
+                return Ok(());
+            }
+            Value::String(x) => {
+                rendered.push_str(x);
+                return Ok(());
+            }
+            Value::Number(x) => {
+                match &self.option.number_format {
+                    Some(number_format) => rendered.push_str(&number_format(x)),
+                    None => rendered.push_str(&x.to_string()),
+                }
+                return Ok(());
+            }
+            Value::Object(t_hash) => {
+                if let Some(x) = self.raw_value(t_hash) {
+                    rendered.push_str(x);
+                    return Ok(());
+                }
+            }
+            Value::Array(_) => {}
+        }
+
+        // Arrays & Objects are rendered recursively, limit the depth to
+        // prevent stack overflow on deeply nested template hash.
+        if ctx.depth >= self.option.max_depth {
+            return Err(TemplateNestError::MaxDepthExceeded(self.option.max_depth));
+        }
+        ctx.depth += 1;
+        let result = match to_render {
+            Value::Array(t_array) => self
+                .render_array(t_array, ctx)
+                .map(|output| rendered.push_str(&output)),
+            Value::Object(t_hash) => self
+                .template_name(t_hash, ctx)
+                .and_then(|t_path| self.render_template_into(t_path, t_hash, ctx, rendered)),
+            _ => unreachable!(),
+        };
+        ctx.depth -= 1;
+        result
+    }
+
+    /// Renders every element of the array and concatenates them in order,
+    /// separated by array_separator.
+    #[cfg(not(feature = "rayon"))]
+    fn render_array(
+        &self,
+        t_array: &[Value],
+        ctx: &mut RenderContext,
+    ) -> Result<String, TemplateNestError> {
+        let rendered = t_array
+            .iter()
+            .enumerate()
+            .map(|(idx, t)| {
+                ctx.path.push(format!("[{idx}]"));
+                let rendered = self.render_nested(t, ctx);
+                ctx.path.pop();
+                rendered
+            })
+            .collect::<Result<Vec<_>, TemplateNestError>>()?;
+        Ok(rendered.join(self.option.array_separator.as_deref().unwrap_or("")))
+    }
+
+    /// Renders every element of the array in parallel and concatenates them
+    /// in order, separated by array_separator.
+    #[cfg(feature = "rayon")]
+    fn render_array(
+        &self,
+        t_array: &[Value],
+        ctx: &mut RenderContext,
+    ) -> Result<String, TemplateNestError> {
+        let rendered = t_array
+            .par_iter()
+            .enumerate()
+            .map(|(idx, t)| {
+                let mut child = ctx.fork();
+                child.path.push(format!("[{idx}]"));
+                Ok((self.render_nested(t, &mut child)?, child))
+            })
+            .collect::<Result<Vec<_>, TemplateNestError>>()?;
+
+        let mut render = String::new();
+        for (idx, (r, child)) in rendered.into_iter().enumerate() {
+            if idx > 0 {
+                if let Some(separator) = &self.option.array_separator {
+                    render.push_str(separator);
+                }
+            }
+            render.push_str(&r);
+            ctx.join(child);
+        }
+        Ok(render)
+    }
+
+    /// Returns the template name from the name label of t_hash, if there is
+    /// no name label then default_template is returned if it's set.
+    fn template_name<'a>(
+        &'a self,
+        t_hash: &'a Map<String, Value>,
+        ctx: &RenderContext,
+    ) -> Result<&'a str, TemplateNestError> {
+        let label = self.label(ctx);
+        let t_label: &Value = match t_hash.get(label) {
+            Some(t_label) => t_label,
+            None => {
+                return match &self.option.default_template {
+                    Some(default_template) => Ok(default_template),
+                    None => Err(TemplateNestError::NoNameLabel {
+                        label: label.to_string(),
+                        path: ctx.breadcrumb(),
+                    }),
+                }
+            }
+        };
+
+        // template name/path must contain a string, or a number in the
+        // label_map if it's set.
+        let label_map_path = match (t_label, &self.option.label_map) {
+            (Value::Number(id), Some(label_map)) => id.as_u64().and_then(|id| label_map.get(&id)),
+            _ => None,
+        };
+        match (t_label, label_map_path) {
+            (Value::String(path), _) => Ok(path),
+            (_, Some(path)) => Ok(path),
+            _ => Err(TemplateNestError::InvalidNameLabel {
+                label: label.to_string(),
+                value: t_label.to_string(),
+            }),
+        }
+    }
+
+    /// Returns the name label to use for the current render.
+    fn label<'a>(&'a self, ctx: &'a RenderContext) -> &'a str {
+        ctx.label.as_deref().unwrap_or(&self.option.label)
+    }
+
+    /// If the template is already being rendered up the chain then it's a
+    /// circular reference.
+    fn check_circular_reference(
+        &self,
+        t_path: &str,
+        ctx: &RenderContext,
+    ) -> Result<(), TemplateNestError> {
+        if self.option.die_on_circular_reference {
+            if let Some(pos) = ctx.templates.iter().position(|t| t == t_path) {
+                let mut cycle = ctx.templates[pos..].to_vec();
+                cycle.push(t_path.to_string());
+                return Err(TemplateNestError::CircularReference(cycle));
+            }
+        }
+        Ok(())
+    }
+
+    /// If a variable in t_hash is not present in the template file and it's
+    /// not the template label then it's a bad param.
+    fn check_bad_params(
+        &self,
+        t_index: &TemplateFileIndex,
+        t_hash: &Map<String, Value>,
+        ctx: &RenderContext,
+    ) -> Result<(), TemplateNestError> {
+        if self.option.die_on_bad_params {
+            // The first bad param in sorted order is reported so that the
+            // error doesn't depend on the iteration order of t_hash.
+            let bad_param = t_hash
+                .keys()
+                .filter(|var_name| {
+                    !t_index.variable_names.contains(*var_name) && !self.is_reserved(var_name, ctx)
+                })
+                .min();
+            if let Some(var_name) = bad_param {
+                return Err(TemplateNestError::BadParams(var_name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the fallback template for the missing template, the fallback
+    /// template has no fallback of its own.
+    fn fallback(&self, t_path: &str) -> Option<&str> {
+        self.option
+            .fallback_template
+            .as_deref()
+            .filter(|fallback| *fallback != t_path)
+    }
+
+    /// Returns true if the key of the template hash is reserved, i.e. it's
+    /// not expected to be a variable of the template.
+    fn is_reserved(&self, key: &str, ctx: &RenderContext) -> bool {
+        key == self.label(ctx)
+            || (self.option.blocks && key == BLOCKS_LABEL)
+            || (self.option.each_loops && (key == KEY_LABEL || key == VALUE_LABEL))
+            || self.option.reserved_keys.contains(key)
+    }
+
+    /// Looks for the variable in t_hash, if it's not provided then looks at
+    /// the template defaults and then the global defaults.
+    fn lookup<'a>(
+        &'a self,
+        t_path: &str,
+        t_hash: &'a Map<String, Value>,
+        name: &str,
+    ) -> Option<&'a Value> {
+        t_hash
+            .get(name)
+            .or_else(|| {
+                self.option
+                    .template_defaults
+                    .get(t_path)
+                    .and_then(|defaults| defaults.get(name))
+            })
+            .or_else(|| self.option.defaults.get(&format!("{t_path}/{name}")))
+            .or_else(|| self.option.defaults.get(name))
+    }
+
+    /// Returns the override of the block from the `BLOCKS` object of t_hash.
+    fn block_override<'a>(&self, t_hash: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+        t_hash.get(BLOCKS_LABEL)?.get(name)
+    }
+
+    /// Returns the classes in the variables joined with spaces, without the
+    /// duplicates.
+    fn classes(&self, t_path: &str, t_hash: &Map<String, Value>, names: &[String]) -> String {
+        let mut classes: Vec<&str> = vec![];
+        for name in names {
+            let values: Vec<&Value> = match self.lookup(t_path, t_hash, name) {
+                Some(Value::Array(values)) => values.iter().collect(),
+                Some(value) => vec![value],
+                None => vec![],
+            };
+            for class in values
+                .into_iter()
+                .filter_map(Value::as_str)
+                .flat_map(str::split_whitespace)
+            {
+                if !classes.contains(&class) {
+                    classes.push(class);
+                }
+            }
+        }
+        classes.join(" ")
+    }
+
+    /// Returns an array of template hashes that renders the each loop, one for
+    /// every entry of the object.
+    fn each_value(
+        &self,
+        t_path: &str,
+        t_hash: &Map<String, Value>,
+        var: &TemplateFileVariable,
+        template: &str,
+        ctx: &RenderContext,
+    ) -> Result<Option<Value>, TemplateNestError> {
+        let object = match self.lookup(t_path, t_hash, &var.name) {
+            None => return Ok(None),
+            Some(Value::Null) => return Ok(Some(Value::Array(vec![]))),
+            Some(Value::Object(object)) => object,
+            Some(value) => {
+                return Err(TemplateNestError::InvalidEachValue {
+                    variable: var.name.to_string(),
+                    value: value.to_string(),
+                })
+            }
+        };
+        let entries = object
+            .iter()
+            .map(|(key, value)| {
+                let mut entry = Map::new();
+                entry.insert(self.label(ctx).to_string(), template.into());
+                entry.insert(KEY_LABEL.to_string(), key.as_str().into());
+                entry.insert(VALUE_LABEL.to_string(), value.clone());
+                Value::Object(entry)
+            })
+            .collect();
+        Ok(Some(Value::Array(entries)))
+    }
+
+    /// Renders the value of a variable, strings are escaped & everything else
+    /// is rendered as a template hash.
+    fn render_variable(
+        &self,
+        value: &Value,
+        var: &TemplateFileVariable,
+        t_path: &str,
+        ctx: &mut RenderContext,
+    ) -> Result<String, TemplateNestError> {
+        let mut r: String = match value {
+            Value::Bool(x) if self.option.bool_as_attribute => match x {
+                true => var.name.to_string(),
+                false => "".to_string(),
+            },
+            Value::String(text) if !var.filters.is_empty() => {
+                let mut filters = var.filters.iter();
+                // The first filter borrows the text so it's only copied if
+                // it's escaped.
+                let first = filters.next().unwrap().escape(text);
+                filters
+                    .fold(first, |text, filter| match filter.escape(&text) {
+                        Cow::Borrowed(_) => text,
+                        Cow::Owned(escaped) => Cow::Owned(escaped),
+                    })
+                    .into_owned()
+            }
+            Value::String(text) => match self.option.escape_html && !var.raw {
+                true => self.option.escaper.escape(text).into_owned(),
+                false => text.to_string(),
+            },
+            _ => {
+                ctx.path.push(format!(".{}", var.name));
+                let rendered = self.render_nested(value, ctx);
+                ctx.path.pop();
+                rendered.map_err(|err| match err {
+                    // Add the template that referenced the missing template.
+                    TemplateNestError::TemplateFileNotFound(missing) => {
+                        TemplateNestError::TemplateFileNotFoundIn {
+                            missing,
+                            referenced_by: t_path.to_string(),
+                            variable: var.name.to_string(),
+                        }
+                    }
+                    err => err,
+                })?
+            }
+        };
+
+        // If fixed_indent is set then get the indent and insert it after all
+        // the newlines in the rendered string, the existing leading whitespace
+        // of each line is kept so the relative indentation is preserved. Only
+        // '\n' is matched so CRLF line endings are preserved, the indent is
+        // inserted after the '\r\n'.
+        if self.option.fixed_indent && !var.indent.is_empty() {
+            let replacement = format!("\n{}", var.indent);
+            r = r.replace('\n', &replacement);
+        }
+        Ok(r)
+    }
+
+    /// Renders the tokens in the range along with the contents around them,
+    /// i.e. from the end of the token before the range to the start of the
+    /// token after it. The output is appended to rendered.
+    fn render_tokens(
+        &self,
+        t_path: &str,
+        t_hash: &Map<String, Value>,
+        t_index: &TemplateFileIndex,
+        tokens: Range<usize>,
+        rendered: &mut String,
+        ctx: &mut RenderContext,
+    ) -> Result<(), TemplateNestError> {
+        let contents = &t_index.contents;
+
+        // Iterate through all variables and copy the contents between them to
+        // the output, `position` is the end of the last copied region.
+        let mut position = match tokens.start {
+            0 => 0,
+            start => t_index.variables[start - 1].end_position,
+        };
+        let end = match t_index.variables.get(tokens.end) {
+            Some(var) => var.start_position,
+            None => contents.len(),
+        };
+        let mut idx = tokens.start;
+        while idx < tokens.end {
+            let var = &t_index.variables[idx];
+            rendered.push_str(&contents[position..var.start_position]);
+            position = var.end_position;
+            idx += 1;
+
+            match var.kind {
+                // If the variable was escaped then we just remove the
+                // token, not the variable. Comments are removed.
+                TokenKind::Escaped
+                | TokenKind::Comment
+                | TokenKind::EndIf(_)
+                | TokenKind::EndBlock(_) => continue,
+                // If the condition is false then skip the whole section
+                // and all the variables inside it, otherwise just remove
+                // the token.
+                TokenKind::If(endif_idx) => {
+                    if !is_truthy(self.lookup(t_path, t_hash, &var.name)) {
+                        position = t_index.variables[endif_idx].end_position;
+                        idx = endif_idx + 1;
+                    }
+                    continue;
+                }
+                // If the block is overridden then the override replaces the
+                // whole block, otherwise just remove the token and render
+                // the default contents.
+                TokenKind::Block(endblock_idx) => {
+                    if let Some(value) = self.block_override(t_hash, &var.name) {
+                        rendered.push_str(&self.render_variable(value, var, t_path, ctx)?);
+                        position = t_index.variables[endblock_idx].end_position;
+                        idx = endblock_idx + 1;
+                    }
+                    continue;
+                }
+                TokenKind::Classes(ref names) => {
+                    let classes = self.classes(t_path, t_hash, names);
+                    match self.option.escape_html {
+                        true => rendered.push_str(&self.option.escaper.escape(&classes)),
+                        false => rendered.push_str(&classes),
+                    }
+                    continue;
+                }
+                TokenKind::Each(ref template) => {
+                    if let Some(value) = self.each_value(t_path, t_hash, var, template, ctx)? {
+                        rendered.push_str(&self.render_variable(&value, var, t_path, ctx)?);
+                        continue;
+                    }
+                    if let Some(on_missing) = &self.on_missing {
+                        on_missing(t_path, &var.name);
+                    }
+                    if self.option.die_on_unused_variables {
+                        return Err(TemplateNestError::MissingVariable(var.name.to_string()));
+                    }
+                    continue;
+                }
+                TokenKind::Variable => {}
+            }
+
+            // Look for the variable in t_hash and then the defaults. If
+            // the variable doesn't exist then it's replaced by an empty
+            // string.
+            if let Some(value) = self.lookup(t_path, t_hash, &var.name) {
+                let rendered_value = self.render_variable(value, var, t_path, ctx)?;
+                if self.option.mark_defaults && !t_hash.contains_key(&var.name) {
+                    let (start, end) = &self.option.comment_delimiters;
+                    rendered.push_str(&format!(
+                        "{start} DEFAULT {} {end}{rendered_value}{start} END DEFAULT {} {end}",
+                        var.name, var.name
+                    ));
+                } else {
+                    rendered.push_str(&rendered_value);
+                }
+                continue;
+            }
+            if let Some(on_missing) = &self.on_missing {
+                on_missing(t_path, &var.name);
+            }
+            if self.option.die_on_unused_variables {
+                return Err(TemplateNestError::MissingVariable(var.name.to_string()));
+            } else if self.option.keep_unresolved {
+                rendered.push_str(&contents[var.start_position..var.end_position]);
+            }
+        }
+        rendered.push_str(&contents[position..end]);
+        Ok(())
+    }
+
+    /// Renders the template with the variables in t_hash.
+    fn render_template(
+        &self,
+        t_path: &str,
+        t_hash: &Map<String, Value>,
+        ctx: &mut RenderContext,
+    ) -> Result<String, TemplateNestError> {
+        let mut rendered = String::new();
+        self.render_template_into(t_path, t_hash, ctx, &mut rendered)?;
+        Ok(rendered)
+    }
+
+    /// Renders the template with the variables in t_hash & appends the output
+    /// to rendered.
+    fn render_template_into(
+        &self,
+        t_path: &str,
+        t_hash: &Map<String, Value>,
+        ctx: &mut RenderContext,
+        rendered: &mut String,
+    ) -> Result<(), TemplateNestError> {
+        self.check_circular_reference(t_path, ctx)?;
+        ctx.templates.push(t_path.to_string());
+        if let Some(deps) = &mut ctx.deps {
+            deps.insert(t_path.to_string());
+        }
+
+        let t_hash = match &self.pre_render {
+            Some(pre_render) => {
+                let mut t_hash = t_hash.clone();
+                pre_render(t_path, &mut t_hash);
+                Cow::Owned(t_hash)
+            }
+            None => Cow::Borrowed(t_hash),
+        };
+        let t_hash = t_hash.as_ref();
+
+        // If fallback_template is set then it's rendered in place of a
+        // missing template, otherwise if missing_template_is_empty is set then
+        // a missing template is rendered as an empty string.
+        let t_index = match self.template_index(t_path) {
+            Err(TemplateNestError::TemplateFileNotFound(missing)) => {
+                ctx.templates.pop();
+                return match self.fallback(t_path) {
+                    Some(fallback) => {
+                        let mut fallback_hash = Map::new();
+                        fallback_hash.insert(MISSING_NAME_LABEL.to_string(), t_path.into());
+                        self.render_template_into(fallback, &fallback_hash, ctx, rendered)
+                    }
+                    None if self.option.missing_template_is_empty => Ok(()),
+                    None => Err(TemplateNestError::TemplateFileNotFound(missing)),
+                };
+            }
+            t_index => t_index?,
+        };
+        self.check_bad_params(&t_index, t_hash, ctx)?;
+
+        let contents = &t_index.contents;
+        let capacity = match self.option.capacity_hint {
+            Some(capacity_hint) if ctx.templates.len() == 1 => capacity_hint,
+            _ => estimate_capacity(contents, t_hash),
+        };
+        rendered.reserve(capacity);
+
+        if let Some((prefix, _)) = &self.option.template_wrapper {
+            rendered.push_str(prefix);
+        }
+
+        // Add lables to the rendered string if show_labels is true, if
+        // label_templates is set then only to the templates in it.
+        let show_labels = self.option.show_labels
+            && self
+                .option
+                .label_templates
+                .as_ref()
+                .map_or(true, |label_templates| label_templates.contains(t_path));
+        if show_labels {
+            rendered.push_str(&format!(
+                "{} BEGIN {} {}\n",
+                self.option.comment_delimiters.0, t_path, self.option.comment_delimiters.1
+            ));
+        }
+
+        self.render_tokens(
+            t_path,
+            t_hash,
+            &t_index,
+            0..t_index.variables.len(),
+            rendered,
+            ctx,
+        )?;
+
+        if show_labels {
+            // The END label is on its own line, it ends with a newline like
+            // the template file unless the newline was dropped.
+            if !rendered.ends_with('\n') {
+                rendered.push('\n');
+            }
+            rendered.push_str(&format!(
+                "{} END {} {}",
+                self.option.comment_delimiters.0, t_path, self.option.comment_delimiters.1
+            ));
+            if !self.option.trim_trailing {
+                rendered.push('\n');
+            }
+        }
+
+        if let Some((_, suffix)) = &self.option.template_wrapper {
+            rendered.push_str(suffix);
+        }
+
+        ctx.templates.pop();
+        Ok(())
+    }
+}
+
+/// Returns true if the template name resolves to a path inside the template
+/// directories, i.e. it's relative & has no `..` components. The check is on
+/// the name so templates symlinked into the directories are allowed.
+fn is_contained(template_name: &str) -> bool {
+    Path::new(template_name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Returns the pattern that captures a token between the delimiters. If
+/// escape_char is set then an end delimiter prefixed with it doesn't end the
+/// token, outside of a token it's matched without a capture.
+fn token_pattern(start: &str, end: &str, escape_char: &str) -> String {
+    match escape_char.is_empty() {
+        true => format!("(?s){start}(.+?){end}"),
+        false => {
+            let escaped_end = format!("{}(?:{end})", regex::escape(escape_char));
+            format!("(?s){start}((?:{escaped_end}|.)+?){end}|{escaped_end}")
+        }
+    }
+}
+
+/// Returns the pattern that captures the end delimiter of an escaped end
+/// delimiter.
+fn escaped_end_pattern(end: &str, escape_char: &str) -> String {
+    format!("{}({end})", regex::escape(escape_char))
+}
+
+/// Parses the delimiters directive on the first line of contents, i.e.
+/// `<!--%%DELIMITERS [[ ]] %%-->` with the default delimiters. Returns the
+/// delimiters & the length of the directive line including the newline.
+fn delimiters_directive(
+    option: &TemplateNestOption,
+    contents: &str,
+) -> Option<((String, String), usize)> {
+    let (start, end) = &option.delimiters;
+    let line = contents.lines().next()?;
+    let directive = line
+        .strip_prefix(start.as_str())?
+        .strip_prefix("%DELIMITERS")?
+        .strip_suffix(end.as_str())?
+        .strip_suffix('%')?;
+
+    let mut delimiters = directive.split_whitespace();
+    let delimiters = match (delimiters.next(), delimiters.next(), delimiters.next()) {
+        (Some(start), Some(end), None) => (start.to_string(), end.to_string()),
+        _ => return None,
+    };
+
+    let directive_len = match contents[line.len()..].strip_prefix("\r\n") {
+        Some(_) => line.len() + 2,
+        None => (line.len() + 1).min(contents.len()),
+    };
+    Some((delimiters, directive_len))
+}
+
+/// Collapses 2 or more consecutive blank lines into one, lines with only
+/// whitespace are blank.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut prev_blank = false;
+    for line in text.split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        if !(blank && prev_blank) {
+            collapsed.push_str(line);
+        }
+        prev_blank = blank;
+    }
+    collapsed
+}
+
+/// Estimates the length of the rendered template as the length of the
+/// template & the strings in the template hash, nested values are not
+/// counted as they're rendered separately.
+fn estimate_capacity(contents: &str, t_hash: &Map<String, Value>) -> usize {
+    t_hash
+        .values()
+        .filter_map(Value::as_str)
+        .fold(contents.len(), |capacity, text| capacity + text.len())
+}
+
+/// A conditional section is dropped if the value is absent, false or null.
+fn is_truthy(value: Option<&Value>) -> bool {
+    !matches!(value, None | Some(Value::Null) | Some(Value::Bool(false)))
 }
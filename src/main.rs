@@ -0,0 +1,91 @@
+//! Renders a template hash from a JSON file.
+//!
+//! ```sh
+//! template-nest --dir templates --input page.json
+//! ```
+
+use serde_json::Value;
+use std::{
+    env, fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+use template_nest::{TemplateNest, TemplateNestOption};
+
+const USAGE: &str = "\
+Usage: template-nest [OPTIONS]
+
+Renders the template hash in the input JSON file, reads from stdin if the
+input is not given.
+
+Options:
+  --dir <DIR>              Directory where templates are located [default: templates]
+  --input <FILE>           JSON file with the template hash, '-' reads from stdin
+  --label <LABEL>          Name label used to identify the template [default: TEMPLATE]
+  --extension <EXTENSION>  Template extension [default: html]
+  --show-labels            Prepend & Append labels to every template
+  --fixed-indent           Indent multi-line values to the variable's indent level
+  --die-on-bad-params      Fail if the hash has a variable not in the template
+  -h, --help               Print help";
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1)) {
+        Ok(Some(rendered)) => {
+            println!("{}", rendered);
+            ExitCode::SUCCESS
+        }
+        Ok(None) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("template-nest: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Parses the arguments & renders the input, returns None if help was
+/// requested.
+fn run(mut args: impl Iterator<Item = String>) -> Result<Option<String>, String> {
+    let mut option = TemplateNestOption::default();
+    let mut input = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for `{}`", name))
+        };
+        match arg.as_str() {
+            "--dir" => option.directory = value(&arg)?.into(),
+            "--input" => input = Some(value(&arg)?),
+            "--label" => option.label = value(&arg)?,
+            "--extension" => option.extension = value(&arg)?,
+            "--show-labels" => option.show_labels = true,
+            "--fixed-indent" => option.fixed_indent = true,
+            "--die-on-bad-params" => option.die_on_bad_params = true,
+            "-h" | "--help" => return Ok(None),
+            _ => return Err(format!("unexpected argument `{}`\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let json = match input.as_deref() {
+        None | Some("-") => {
+            let mut json = String::new();
+            io::stdin()
+                .read_to_string(&mut json)
+                .map_err(|err| format!("error reading stdin: {}", err))?;
+            json
+        }
+        Some(path) => {
+            fs::read_to_string(path).map_err(|err| format!("error reading `{}`: {}", path, err))?
+        }
+    };
+    let to_render: Value =
+        serde_json::from_str(&json).map_err(|err| format!("invalid JSON input: {}", err))?;
+
+    let nest = TemplateNest::new(option).map_err(|err| err.to_string())?;
+    nest.render(&to_render)
+        .map(Some)
+        .map_err(|err| err.to_string())
+}
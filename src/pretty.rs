@@ -0,0 +1,110 @@
+//! Reindents rendered HTML, every line is indented by the number of elements
+//! that are open at the start of it. Lines are not split or joined so inline
+//! elements are left as is.
+
+/// Elements that have no closing tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose contents are copied to the output untouched.
+const PRESERVED_ELEMENTS: [&str; 2] = ["pre", "textarea"];
+
+const INDENT: &str = "  ";
+
+/// Tag found in a line of HTML.
+struct Tag<'a> {
+    name: &'a str,
+    closing: bool,
+}
+
+/// Returns the reindented HTML.
+pub(crate) fn reindent(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut depth: usize = 0;
+    // Name of the preserved element we're inside of, its contents are copied
+    // as is until the closing tag is found.
+    let mut preserved: Option<&str> = None;
+
+    for line in html.lines() {
+        if let Some(name) = preserved {
+            output.push_str(line);
+            output.push('\n');
+            if tags(line).any(|tag| tag.closing && tag.name.eq_ignore_ascii_case(name)) {
+                preserved = None;
+                depth = depth.saturating_sub(1);
+            }
+            continue;
+        }
+
+        let line = line.trim_start();
+        if line.is_empty() {
+            output.push('\n');
+            continue;
+        }
+
+        // If a preserved element is left open then the following lines are
+        // its contents, the rest of this line is kept as is too.
+        let opened = PRESERVED_ELEMENTS.iter().copied().find(|name| {
+            let opened = tags(line).filter(|tag| tag.name.eq_ignore_ascii_case(name));
+            opened.fold(
+                0_i32,
+                |open, tag| if tag.closing { open - 1 } else { open + 1 },
+            ) > 0
+        });
+        let line = match opened {
+            Some(_) => line,
+            None => line.trim_end(),
+        };
+
+        // Closing tags at the start of the line belong to the outer level.
+        let mut leading_closes = 0;
+        let mut rest = line;
+        while let Some(tag) = rest.strip_prefix("</") {
+            leading_closes += 1;
+            rest = match tag.find('>') {
+                Some(end) => tag[end + 1..].trim_start(),
+                None => break,
+            };
+        }
+
+        output.push_str(&INDENT.repeat(depth.saturating_sub(leading_closes)));
+        output.push_str(line);
+        output.push('\n');
+
+        for tag in tags(line) {
+            if tag.closing {
+                depth = depth.saturating_sub(1);
+            } else {
+                depth += 1;
+            }
+        }
+        preserved = opened;
+    }
+    output
+}
+
+/// Returns the tags in the line that open or close an element, comments,
+/// doctype, void elements & self-closing tags are skipped.
+fn tags(line: &str) -> impl Iterator<Item = Tag<'_>> {
+    line.match_indices('<').filter_map(move |(start, _)| {
+        let tag = &line[start + 1..];
+        let end = tag.find('>')?;
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+
+        let name_len = tag
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_len];
+        let self_closing = line[..start + 1 + end].ends_with('/');
+        let void = VOID_ELEMENTS.iter().any(|v| v.eq_ignore_ascii_case(name));
+        match name.is_empty() || self_closing || void {
+            true => None,
+            false => Some(Tag { name, closing }),
+        }
+    })
+}
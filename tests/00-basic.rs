@@ -8,3 +8,21 @@ fn initialize() -> Result<(), TemplateNestError> {
     })?;
     Ok(())
 }
+
+#[test]
+fn initialize_with_directory() -> Result<(), TemplateNestError> {
+    TemplateNest::with_directory("templates")?;
+    Ok(())
+}
+
+#[test]
+fn die_on_invalid_delimiters() {
+    for delimiters in [("[%", "%"), ("[%", "%]")] {
+        let nest = TemplateNest::new(TemplateNestOption {
+            directory: "templates".into(),
+            delimiters: (delimiters.0.to_string(), delimiters.1.to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(nest, Err(TemplateNestError::InvalidDelimiters(_))));
+    }
+}
@@ -124,3 +124,58 @@ fn render_array_of_template_hash() -> Result<(), TemplateNestError> {
 
     Ok(())
 }
+
+#[test]
+fn render_simple_page_with_directory() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+    assert_eq!(nest.render(&page)?, nest.render(&page_output)?);
+    Ok(())
+}
+
+#[test]
+fn render_multi_line_token() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "06-multi-line-token",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+    Ok(())
+}
+
+/// Templates in sub-directories are referenced with '/' as the separator on
+/// every platform.
+#[test]
+fn render_template_in_subdirectory() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "output/10-var-at-begin",
+    });
+    assert_eq!(nest.render(&page)?, "Simple Variable");
+    Ok(())
+}
+
+#[test]
+fn render_template_with_bom() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "17-bom",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<!DOCTYPE html>\n<p>Simple Variable</p>"
+    );
+    Ok(())
+}
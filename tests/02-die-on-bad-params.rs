@@ -1,4 +1,5 @@
 use serde_json::json;
+use std::collections::HashSet;
 use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
 
 #[test]
@@ -56,6 +57,7 @@ fn die_on_page_with_bad_params_01() {
 }
 
 #[test]
+#[allow(clippy::single_match)]
 fn live_on_page_with_bad_params() {
     let nest = TemplateNest::new(TemplateNestOption {
         directory: "templates".into(),
@@ -78,3 +80,62 @@ fn live_on_page_with_bad_params() {
         _ => {}
     }
 }
+
+#[test]
+fn die_on_page_with_bad_params_reports_stable_param() {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_bad_params: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  "Simple Component",
+        "z_bad_param": "Bad Param",
+        "b_bad_param": "Bad Param",
+        "m_bad_param": "Bad Param",
+    });
+
+    for _ in 0..16 {
+        match nest.render(&page) {
+            Err(TemplateNestError::BadParams(param)) => assert_eq!(param, "b_bad_param"),
+            _ => panic!("Must return TemplateNestError::BadParams on bad params error."),
+        }
+    }
+}
+
+/// A variable named like the label is substituted with the label's value &
+/// the reserved keys are not bad params.
+#[test]
+fn live_on_page_with_reserved_keys() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .label("NAME")
+            .reserved_keys(HashSet::from(["id".to_string()]))
+            .die_on_bad_params(true)
+            .build(),
+    )?;
+    let page = json!({
+        "NAME": "28-label-variable",
+        "id": 7,
+        "title": "Title",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        r#"<h1 id="28-label-variable">Title</h1>"#
+    );
+
+    let page = json!({
+        "NAME": "28-label-variable",
+        "TEMPLATE": "28-label-variable",
+        "title": "Title",
+    });
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::BadParams(param)) if param == "TEMPLATE"
+    ));
+    Ok(())
+}
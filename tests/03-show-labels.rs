@@ -1,4 +1,5 @@
 use serde_json::json;
+use std::collections::HashSet;
 use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
 
 #[cfg(test)]
@@ -70,3 +71,91 @@ fn render_with_show_labels_alt_delimiters() -> Result<(), TemplateNestError> {
     assert_eq!(nest.render(&page)?, nest_no_labels.render(&page_output)?,);
     Ok(())
 }
+
+/// The labels of a nested template are indented along with its contents.
+#[test]
+fn render_with_show_labels_fixed_indent() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        show_labels: true,
+        fixed_indent: true,
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "16-relative-indent-page",
+        "simple_component":  {
+            "TEMPLATE": "16-relative-indent-component",
+        }
+    });
+
+    assert_eq!(
+        nest.render(&page)?,
+        "<!-- BEGIN 16-relative-indent-page -->
+<section>
+    <!-- BEGIN 16-relative-indent-component -->
+    <ul>
+      <li>
+        <a href=\"/\">Home</a>
+      </li>
+    </ul>
+    <!-- END 16-relative-indent-component -->
+</section>
+<!-- END 16-relative-indent-page -->"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_label_templates() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        show_labels: true,
+        label_templates: Some(HashSet::from(["01-simple-component".to_string()])),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+
+    let rendered = nest.render(&page)?;
+    assert!(!rendered.contains("<!-- BEGIN 00-simple-page -->"));
+    assert!(!rendered.contains("<!-- END 00-simple-page -->"));
+    assert!(rendered.contains(
+        "<!-- BEGIN 01-simple-component -->
+<p>Simple Variable in Simple Component</p>
+<!-- END 01-simple-component -->"
+    ));
+    Ok(())
+}
+
+#[test]
+fn render_with_template_wrapper() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .template_wrapper("\u{200b}", "\u{200c}")
+            .build(),
+    )?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": [
+            {
+                "TEMPLATE": "01-simple-component",
+                "variable": "Simple Variable",
+            },
+            { "RAW": "raw" },
+        ],
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "\u{200b}<p>\u{200b}<p>Simple Variable</p>\u{200c}raw</p>\u{200c}"
+    );
+    Ok(())
+}
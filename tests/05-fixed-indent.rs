@@ -1,4 +1,5 @@
 use serde_json::json;
+use std::error::Error;
 use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
 
 #[cfg(test)]
@@ -87,3 +88,100 @@ fn render_complex_page_with_fixed_indent() -> Result<(), TemplateNestError> {
     assert_eq!(nest.render(&page)?, nest.render(&page_output)?,);
     Ok(())
 }
+
+#[test]
+fn render_crlf_page_with_fixed_indent() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        fixed_indent: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    let page = json!({
+        "TEMPLATE": "07-crlf-page",
+        "component":  {
+            "TEMPLATE": "07-crlf-component",
+            "variable": "Simple\r\nVariable",
+        }
+    });
+
+    assert_eq!(
+        nest.render(&page)?,
+        "<div>\r\n  <p>\r\n    Simple\r\n    Variable\r\n  </p>\r\n</div>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_tab_indented_page_with_fixed_indent() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        fixed_indent: true,
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "15-tab-indent-page",
+        "simple_component":  {
+            "TEMPLATE": "15-tab-indent-component",
+        }
+    });
+
+    assert_eq!(
+        nest.render(&page)?,
+        "<div>\n\t<ul>\n\t\t<li>Item</li>\n\t</ul>\n</div>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_multi_byte_prefix_with_fixed_indent() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(
+        dir.path().join("page.html"),
+        "<p>\u{00e9}<!--% component %--></p>",
+    )?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        fixed_indent: true,
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "page",
+        "component": ["a\n", "b"],
+    });
+    assert_eq!(nest.render(&page)?, "<p>\u{00e9}a\n    b</p>");
+    Ok(())
+}
+
+/// The indent of the variable is prepended to every line of the value, the
+/// indent of the lines in the component is kept.
+#[test]
+fn render_nested_indentation_with_fixed_indent() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        fixed_indent: true,
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "16-relative-indent-page",
+        "simple_component":  {
+            "TEMPLATE": "16-relative-indent-component",
+        }
+    });
+
+    assert_eq!(
+        nest.render(&page)?,
+        "<section>
+    <ul>
+      <li>
+        <a href=\"/\">Home</a>
+      </li>
+    </ul>
+</section>"
+    );
+    Ok(())
+}
@@ -1,12 +1,12 @@
 use serde_json::json;
-use std::{fs, path::PathBuf};
+use std::{error::Error, fs, path::PathBuf};
 use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
 #[test]
-fn render_with_escaped_variable() -> Result<(), TemplateNestError> {
+fn render_with_escaped_variable() -> Result<(), Box<dyn Error>> {
     let nest = TemplateNest::new(TemplateNestOption {
         directory: "templates".into(),
         token_escape_char: "\\".to_string(),
@@ -30,7 +30,7 @@ fn render_with_escaped_variable() -> Result<(), TemplateNestError> {
 
 /// Test if we can handle files where token is at the beginning of the file.
 #[test]
-fn render_with_escaped_variable_at_start() -> Result<(), TemplateNestError> {
+fn render_with_escaped_variable_at_start() -> Result<(), Box<dyn Error>> {
     let nest = TemplateNest::new(TemplateNestOption {
         directory: "templates".into(),
         token_escape_char: "\\".to_string(),
@@ -48,3 +48,84 @@ fn render_with_escaped_variable_at_start() -> Result<(), TemplateNestError> {
     assert_eq!(nest.render(&page)?, fs::read_to_string(output_file)?.trim());
     Ok(())
 }
+
+/// Test if we can handle files where the escaped token is preceded only by the
+/// escape character.
+#[test]
+fn render_with_escaped_token_at_start() -> Result<(), Box<dyn Error>> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        token_escape_char: "\\".to_string(),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "04-escaped-var-at-begin",
+        "variable": "Simple Variable",
+    });
+
+    let mut output_file: PathBuf = "templates".into();
+    output_file.push("output/14-escaped-var-at-begin.html");
+
+    assert_eq!(nest.render(&page)?, fs::read_to_string(output_file)?.trim());
+    Ok(())
+}
+
+/// Test multi-byte escape character placed after a multi-byte character.
+#[test]
+fn render_with_multi_byte_escape_char() -> Result<(), Box<dyn Error>> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        token_escape_char: "»".to_string(),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "05-multi-byte-token-escape",
+        "variable": "Simple Variable",
+    });
+
+    let mut output_file: PathBuf = "templates".into();
+    output_file.push("output/15-multi-byte-token-escape.html");
+
+    assert_eq!(nest.render(&page)?, fs::read_to_string(output_file)?.trim());
+    Ok(())
+}
+
+/// Test if an escaped end delimiter in the text is emitted without the escape
+/// char.
+#[test]
+fn render_with_escaped_end_delimiter() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        token_escape_char: "\\".to_string(),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "21-escaped-end-delimiter",
+        "variable": "Simple Variable",
+    });
+
+    assert_eq!(nest.render(&page)?, "<p>50%--> done Simple Variable</p>");
+    Ok(())
+}
+
+/// Test if an escaped end delimiter doesn't end the token.
+#[test]
+fn render_with_escaped_end_delimiter_in_token() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        token_escape_char: "\\".to_string(),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "31-escaped-end-in-token",
+        "a %--> b": "Escaped",
+        "variable": "Simple Variable",
+    });
+
+    assert_eq!(nest.render(&page)?, "<p>Escaped Simple Variable</p>");
+    Ok(())
+}
@@ -28,3 +28,177 @@ fn render_with_defaults() -> Result<(), TemplateNestError> {
     assert_eq!(nest.render(&page)?, nest.render(&page_output)?,);
     Ok(())
 }
+
+#[test]
+fn render_with_template_defaults() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        defaults: HashMap::from([("variable".to_string(), json!("Global Variable"))]),
+        template_defaults: HashMap::from([(
+            "01-simple-component".to_string(),
+            HashMap::from([("variable".to_string(), json!("Component Variable"))]),
+        )]),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+        }
+    });
+    let output = nest.render(&page)?;
+    assert!(output.contains("<p>Global Variable</p>"));
+    assert!(output.contains("<p>Component Variable</p>"));
+
+    let component = json!({
+        "TEMPLATE":"01-simple-component",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&component)?, "<p>Simple Variable</p>");
+    Ok(())
+}
+
+/// Defaults keyed by `template/variable` only apply within that template.
+#[test]
+fn render_with_namespaced_defaults() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        defaults: HashMap::from([
+            ("variable".to_string(), json!("Global Variable")),
+            (
+                "01-simple-component/variable".to_string(),
+                json!("Component Variable"),
+            ),
+        ]),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+        }
+    });
+    let output = nest.render(&page)?;
+    assert!(output.contains("<p>Global Variable</p>"));
+    assert!(output.contains("<p>Component Variable</p>"));
+    Ok(())
+}
+
+#[test]
+fn render_with_defaults_from_file() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .defaults(TemplateNestOption::defaults_from_file(
+                "templates/defaults/01-simple-page.json",
+            )?)
+            .build(),
+    )?;
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+
+    assert_eq!(nest.render(&page)?, nest.render(&page_output)?,);
+    Ok(())
+}
+
+#[test]
+fn die_on_invalid_defaults_file() {
+    let defaults = TemplateNestOption::defaults_from_file("templates/defaults/02-invalid.json");
+    assert!(matches!(
+        defaults,
+        Err(TemplateNestError::InvalidDefaultsFile { .. })
+    ));
+}
+
+#[test]
+fn render_with_defaults_from_env() -> Result<(), TemplateNestError> {
+    std::env::set_var("TEMPLATE_NEST_TEST_VARIABLE", "Environment Variable");
+
+    let mut defaults =
+        TemplateNestOption::defaults_from_file("templates/defaults/01-simple-page.json")?;
+    defaults.extend(TemplateNestOption::defaults_from_env("TEMPLATE_NEST_TEST_"));
+    assert_eq!(
+        defaults.get("variable"),
+        Some(&json!("Environment Variable"))
+    );
+
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .defaults(defaults)
+            .build(),
+    )?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Environment Variable</p>");
+    Ok(())
+}
+
+/// The environment variable named exactly the prefix has no variable name.
+#[test]
+fn defaults_from_env_skips_prefix() {
+    std::env::set_var("TEMPLATE_NEST_PREFIX_", "Prefix");
+    std::env::set_var("TEMPLATE_NEST_PREFIX_NAME", "Name");
+
+    let defaults = TemplateNestOption::defaults_from_env("TEMPLATE_NEST_PREFIX_");
+    assert_eq!(
+        defaults,
+        HashMap::from([("name".to_string(), json!("Name"))])
+    );
+}
+
+/// Environment variables that are not valid UTF-8 are skipped.
+#[cfg(unix)]
+#[test]
+fn defaults_from_env_skips_non_utf8() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    std::env::set_var("TEMPLATE_NEST_NON_UTF8_NAME", "Name");
+    std::env::set_var(
+        "TEMPLATE_NEST_NON_UTF8_VALUE",
+        OsStr::from_bytes(b"Caf\xe9"),
+    );
+    std::env::set_var(OsStr::from_bytes(b"NON_UTF8_\xff"), "Other");
+
+    let defaults = TemplateNestOption::defaults_from_env("TEMPLATE_NEST_NON_UTF8_");
+    assert_eq!(
+        defaults,
+        HashMap::from([("name".to_string(), json!("Name"))])
+    );
+}
+
+#[test]
+fn render_with_mark_defaults() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        defaults: HashMap::from([("variable".to_string(), json!("Default Variable"))]),
+        mark_defaults: true,
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable",
+        }
+    });
+    let output = nest.render(&page)?;
+    assert!(output
+        .contains("<p><!-- DEFAULT variable -->Default Variable<!-- END DEFAULT variable --></p>"));
+    assert!(output.contains("<p>Simple Variable</p>"));
+    Ok(())
+}
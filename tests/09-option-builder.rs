@@ -0,0 +1,30 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_option_builder() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .delimiters("<%", "%>")
+            .build(),
+    )?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page-alt-delim",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component-alt-delim",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+
+    let nest_default = TemplateNest::new(TemplateNestOption::builder().build())?;
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+    assert_eq!(nest.render(&page)?, nest_default.render(&page_output)?);
+    Ok(())
+}
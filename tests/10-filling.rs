@@ -0,0 +1,43 @@
+use serde_json::json;
+use template_nest::{filling, filling_list, filling_text, TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_modified_filling() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+
+    let mut simple_page = filling!(
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+    );
+    let mut components = filling_list![];
+    components.push(filling!(
+        "TEMPLATE": "01-simple-component",
+        "variable": filling_text!("Simple Variable in Simple Component")
+    ))?;
+    simple_page.insert("simple_component", components)?;
+
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+    assert_eq!(
+        nest.render(&simple_page.into())?,
+        nest.render(&page_output)?
+    );
+    Ok(())
+}
+
+#[test]
+fn insert_into_text_filling() {
+    let mut text = filling_text!("Simple Variable");
+    match text.insert("variable", "Simple Variable") {
+        Err(TemplateNestError::InvalidFillingOperation(_)) => {}
+        _ => panic!("Must not insert into a text filling."),
+    }
+    match text.push("Simple Variable") {
+        Err(TemplateNestError::InvalidFillingOperation(_)) => {}
+        _ => panic!("Must not push into a text filling."),
+    }
+}
@@ -0,0 +1,91 @@
+use serde_json::json;
+use std::error::Error;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_trim_trailing() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        trim_trailing: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn render_without_trim_trailing() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        trim_trailing: false,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>\n");
+    Ok(())
+}
+
+/// Only the output of the outermost template is trimmed, the whitespace at
+/// the end of a nested template is kept.
+#[test]
+fn render_nested_fragment_ending_in_whitespace() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(
+        dir.path().join("sentence.html"),
+        "<p><!--% first %--><!--% second %--></p>\n",
+    )?;
+    std::fs::write(dir.path().join("word.html"), "<!--% word %--> \n")?;
+
+    let nest = TemplateNest::with_directory(dir.path())?;
+    let page = json!({
+        "TEMPLATE": "sentence",
+        "first": { "TEMPLATE": "word", "word": "Hello" },
+        "second": { "TEMPLATE": "word", "word": "World" },
+    });
+    assert_eq!(nest.render(&page)?, "<p>Hello World </p>");
+
+    let page = json!({ "TEMPLATE": "word", "word": "Hello" });
+    assert_eq!(nest.render(&page)?, "Hello");
+    Ok(())
+}
+
+#[test]
+fn render_with_trim_leading() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "30-leading-blank-line",
+        "variable": "Simple Variable",
+    });
+
+    let nest = TemplateNest::with_directory("templates")?;
+    assert_eq!(nest.render(&page)?, "\n\n<p>Simple Variable</p>");
+
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        trim_leading: true,
+        ..Default::default()
+    })?;
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+
+    let mut out = "<div>".to_string();
+    nest.render_into(&page, &mut out)?;
+    assert_eq!(out, "<div><p>Simple Variable</p>");
+
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        trim_leading: true,
+        trim_trailing: false,
+        ..Default::default()
+    })?;
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>\n");
+    Ok(())
+}
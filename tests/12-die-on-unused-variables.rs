@@ -0,0 +1,58 @@
+use serde_json::json;
+use std::collections::HashMap;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[test]
+fn die_on_page_with_missing_variable() {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_unused_variables: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  "Simple Component",
+    });
+
+    match nest.render(&page) {
+        Err(TemplateNestError::MissingVariable(name)) => assert_eq!(name, "variable"),
+        Err(_) => {
+            panic!("Must return TemplateNestError::MissingVariable on missing variable.")
+        }
+        Ok(_) => {
+            panic!("All variables in template file must be provided.")
+        }
+    }
+}
+
+#[test]
+fn live_on_page_with_defaulted_variable() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_unused_variables: true,
+        defaults: HashMap::from([("variable".to_string(), json!("Simple Variable"))]),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  "Simple Component",
+    });
+    nest.render(&page)?;
+    Ok(())
+}
+
+#[test]
+fn live_on_page_with_escaped_variable() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_unused_variables: true,
+        token_escape_char: "\\".to_string(),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component-token-escape",
+    });
+    nest.render(&page)?;
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_bool_as_text() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        bool_as_text: true,
+        ..Default::default()
+    })?;
+
+    for (value, output) in [
+        (json!(true), "<p>true</p>"),
+        (json!(false), "<p>false</p>"),
+        (json!(null), "<p></p>"),
+    ] {
+        let page = json!({
+            "TEMPLATE": "01-simple-component",
+            "variable": value,
+        });
+        assert_eq!(nest.render(&page)?, output);
+    }
+    Ok(())
+}
+
+#[test]
+fn render_bool_as_empty() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        bool_as_text: false,
+        ..Default::default()
+    })?;
+
+    for value in [json!(true), json!(false), json!(null)] {
+        let page = json!({
+            "TEMPLATE": "01-simple-component",
+            "variable": value,
+        });
+        assert_eq!(nest.render(&page)?, "<p></p>");
+    }
+    Ok(())
+}
+
+#[test]
+fn render_bool_as_attribute() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .bool_as_attribute(true)
+            .build(),
+    )?;
+
+    for (value, output) in [
+        (json!(true), r#"<input type="checkbox" checked>"#),
+        (json!(false), r#"<input type="checkbox" >"#),
+        (json!(null), r#"<input type="checkbox" >"#),
+    ] {
+        let page = json!({
+            "TEMPLATE": "22-checkbox",
+            "checked": value,
+        });
+        assert_eq!(nest.render(&page)?, output);
+    }
+
+    let page = json!({ "TEMPLATE": "22-checkbox" });
+    assert_eq!(nest.render(&page)?, r#"<input type="checkbox" >"#);
+    Ok(())
+}
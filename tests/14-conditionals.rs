@@ -0,0 +1,59 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn nest() -> Result<TemplateNest, TemplateNestError> {
+    TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        conditionals: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn render_conditional_true() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "08-conditional",
+        "flag": true,
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest()?.render(&page)?, "<p>Always Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn render_conditional_false() -> Result<(), TemplateNestError> {
+    for flag in [json!(false), json!(null)] {
+        let page = json!({
+            "TEMPLATE": "08-conditional",
+            "flag": flag,
+            "variable": "Simple Variable",
+        });
+        assert_eq!(nest()?.render(&page)?, "<p>Always</p>");
+    }
+    Ok(())
+}
+
+#[test]
+fn render_conditional_missing() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "08-conditional",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest()?.render(&page)?, "<p>Always</p>");
+    Ok(())
+}
+
+/// Conditional tokens are plain variables unless conditionals are enabled.
+#[test]
+fn render_without_conditionals() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "08-conditional",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Always Simple Variable</p>");
+    Ok(())
+}
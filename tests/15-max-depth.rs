@@ -0,0 +1,51 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn nested_page(depth: usize) -> serde_json::Value {
+    let mut page = json!("Simple Variable");
+    for _ in 0..depth {
+        page = json!({
+            "TEMPLATE": "01-simple-component",
+            "variable": page,
+        });
+    }
+    page
+}
+
+#[test]
+fn die_on_page_exceeding_max_depth() {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        max_depth: 4,
+        ..Default::default()
+    })
+    .unwrap();
+
+    match nest.render(&nested_page(5)) {
+        Err(TemplateNestError::MaxDepthExceeded(4)) => {}
+        Err(_) => {
+            panic!("Must return TemplateNestError::MaxDepthExceeded on deeply nested hash.")
+        }
+        Ok(_) => {
+            panic!("Template hash nested beyond max_depth must not render.")
+        }
+    }
+}
+
+#[test]
+fn render_page_within_max_depth() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        max_depth: 4,
+        ..Default::default()
+    })?;
+
+    assert_eq!(
+        nest.render(&nested_page(4))?,
+        "<p><p><p><p>Simple Variable</p></p></p></p>"
+    );
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn die_on_circular_reference() {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_circular_reference: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let page = json!({
+        "TEMPLATE": "09-circular-a",
+        "b": {
+            "TEMPLATE": "09-circular-b",
+            "a": {
+                "TEMPLATE": "09-circular-a",
+            }
+        }
+    });
+
+    match nest.render(&page) {
+        Err(TemplateNestError::CircularReference(cycle)) => {
+            assert_eq!(
+                cycle,
+                vec!["09-circular-a", "09-circular-b", "09-circular-a"]
+            );
+        }
+        Err(_) => {
+            panic!("Must return TemplateNestError::CircularReference on circular reference.")
+        }
+        Ok(_) => {
+            panic!("Template must not be rendered within itself.")
+        }
+    }
+}
+
+#[test]
+fn live_on_circular_reference() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "09-circular-a",
+        "b": {
+            "TEMPLATE": "09-circular-b",
+            "a": {
+                "TEMPLATE": "09-circular-a",
+            }
+        }
+    });
+    assert_eq!(nest.render(&page)?, "<div><span><div></div></span></div>");
+    Ok(())
+}
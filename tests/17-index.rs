@@ -0,0 +1,221 @@
+use serde_json::json;
+use std::error::Error;
+use template_nest::{
+    TemplateNest, TemplateNestError, TemplateNestOption, TemplateToken, TemplateTokenKind,
+};
+use walkdir::WalkDir;
+
+/// Every template file in the directory must be indexed, this test runs with
+/// and without the `rayon` feature.
+#[test]
+fn index_all_templates() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+
+    for entry in WalkDir::new("templates")
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let name = entry
+            .path()
+            .strip_prefix("templates")
+            .unwrap()
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        if let Some(name) = name.strip_suffix(".html") {
+            nest.render(&json!({ "TEMPLATE": name }))?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn list_templates() -> Result<(), TemplateNestError> {
+    for lazy in [false, true] {
+        let nest = TemplateNest::new(TemplateNestOption {
+            directory: "templates".into(),
+            lazy,
+            ..Default::default()
+        })?;
+
+        let templates = nest.list_templates();
+        for name in [
+            "00-simple-page",
+            "01-simple-component",
+            "output/01-simple-page",
+        ] {
+            assert!(templates.contains(&name.to_string()));
+        }
+        assert!(!templates.contains(&"30-main".to_string()));
+
+        let mut sorted = templates.clone();
+        sorted.sort();
+        assert_eq!(templates, sorted);
+    }
+    Ok(())
+}
+
+#[test]
+fn contains_template() -> Result<(), TemplateNestError> {
+    for lazy in [false, true] {
+        let nest = TemplateNest::new(TemplateNestOption {
+            directory: "templates".into(),
+            lazy,
+            ..Default::default()
+        })?;
+
+        assert!(nest.contains_template("00-simple-page"));
+        assert!(nest.contains_template("output/01-simple-page"));
+        assert!(!nest.contains_template("00-simple-page-not-found"));
+    }
+    Ok(())
+}
+
+#[test]
+fn cache_stats() -> Result<(), TemplateNestError> {
+    let templates: Vec<_> = WalkDir::new("templates")
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension() == Some("html".as_ref()))
+        .collect();
+
+    let nest = TemplateNest::with_directory("templates")?;
+    let stats = nest.cache_stats();
+    assert_eq!(stats.templates, templates.len());
+    assert!(stats.variables > 0);
+
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        lazy: true,
+        ..Default::default()
+    })?;
+    assert_eq!(nest.cache_stats().templates, 0);
+    nest.render(&json!({ "TEMPLATE": "01-simple-component" }))?;
+    let stats = nest.cache_stats();
+    assert_eq!(stats.templates, 1);
+    assert_eq!(stats.variables, 1);
+    assert_eq!(stats.bytes, 26);
+
+    // A leading BOM, the delimiters directive line & the newline at the end
+    // of the file are not counted.
+    nest.warm(&["17-bom", "18-custom-delimiters"])?;
+    let stats = nest.cache_stats();
+    assert_eq!(stats.templates, 3);
+    assert_eq!(stats.bytes, 26 + 42 + 57);
+    Ok(())
+}
+
+#[test]
+fn max_walk_depth() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let deep = dir.path().join("a/b/c");
+    std::fs::create_dir_all(&deep)?;
+    std::fs::write(dir.path().join("top.html"), "<p>Top</p>")?;
+    std::fs::write(dir.path().join("a/b/middle.html"), "<p>Middle</p>")?;
+    std::fs::write(deep.join("deep.html"), "<p>Deep</p>")?;
+
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory(dir.path())
+            .max_walk_depth(3)
+            .build(),
+    )?;
+    assert_eq!(nest.list_templates(), vec!["a/b/middle", "top"]);
+
+    let nest = TemplateNest::with_directory(dir.path())?;
+    assert_eq!(
+        nest.list_templates(),
+        vec!["a/b/c/deep", "a/b/middle", "top"]
+    );
+    Ok(())
+}
+
+#[test]
+fn file_filter() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    std::fs::create_dir(dir.path().join("_private"))?;
+    std::fs::write(dir.path().join("page.html"), "<p>Page</p>")?;
+    std::fs::write(dir.path().join("page.bak.html"), "<p>Backup</p>")?;
+    std::fs::write(dir.path().join("_private/secret.html"), "<p>Secret</p>")?;
+
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory(dir.path())
+            .file_filter(|path| {
+                !path.to_string_lossy().ends_with(".bak.html")
+                    && !path.components().any(|c| c.as_os_str() == "_private")
+            })
+            .build(),
+    )?;
+    assert_eq!(nest.list_templates(), vec!["page"]);
+    Ok(())
+}
+
+#[test]
+fn template_tokens() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    assert_eq!(
+        nest.template_tokens("00-simple-page")?,
+        vec![
+            TemplateToken {
+                name: "variable".to_string(),
+                start: 274,
+                end: 293,
+                kind: TemplateTokenKind::Variable,
+            },
+            TemplateToken {
+                name: "simple_component".to_string(),
+                start: 302,
+                end: 329,
+                kind: TemplateTokenKind::Variable,
+            },
+        ]
+    );
+
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        token_escape_char: "\\".to_string(),
+        ..Default::default()
+    })?;
+    let tokens = nest.template_tokens("01-simple-component-token-escape")?;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].kind, TemplateTokenKind::Escaped);
+    assert_eq!((tokens[0].start, tokens[0].end), (3, 4));
+    Ok(())
+}
+
+/// Token positions are in the file, the BOM & the delimiters directive are
+/// counted.
+#[test]
+fn template_tokens_file_positions() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let contents = "\u{feff}<!--%%DELIMITERS [[ ]] %%-->\n<p>[[ variable ]]</p>";
+    std::fs::write(dir.path().join("page.html"), contents)?;
+    let nest = TemplateNest::with_directory(dir.path())?;
+
+    let tokens = nest.template_tokens("page")?;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!((tokens[0].start, tokens[0].end), (35, 49));
+    assert_eq!(&contents[tokens[0].start..tokens[0].end], "[[ variable ]]");
+    Ok(())
+}
+
+#[test]
+fn templates_with_variables() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let templates = nest.templates_with_variables();
+    assert_eq!(
+        templates
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>(),
+        nest.list_templates()
+    );
+
+    let (_, variables) = templates
+        .iter()
+        .find(|(name, _)| name == "00-simple-page")
+        .unwrap();
+    assert_eq!(variables, &vec!["simple_component", "variable"]);
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use serde_json::{json, Value};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Array elements are rendered in parallel if `rayon` feature is enabled, the
+/// output must be the same as rendering each element one after the other.
+#[test]
+fn render_large_array_in_order() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let items: Vec<Value> = (0..100)
+        .map(|i| {
+            json!({
+                "TEMPLATE": "01-simple-component",
+                "variable": format!("Simple Variable {}", i),
+            })
+        })
+        .collect();
+
+    let mut serial = String::new();
+    for item in &items {
+        serial.push_str(&nest.render(item)?);
+    }
+    assert_eq!(nest.render(&Value::Array(items))?, serial);
+    Ok(())
+}
+
+#[test]
+fn die_on_bad_array_element() {
+    let nest = TemplateNest::with_directory("templates").unwrap();
+    let mut items: Vec<Value> = (0..100)
+        .map(|_| json!({ "TEMPLATE": "01-simple-component" }))
+        .collect();
+    items[42] = json!({ "variable": "Simple Variable" });
+
+    match nest.render(&Value::Array(items)) {
+        Err(TemplateNestError::NoNameLabel { .. }) => {}
+        _ => panic!("Must return the error of the bad array element."),
+    }
+}
+
+#[test]
+fn render_array_with_separator() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .array_separator(", ")
+            .build(),
+    )?;
+
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": ["HTML", "CSS", "JavaScript"],
+    });
+    assert_eq!(nest.render(&page)?, "<p>HTML, CSS, JavaScript</p>");
+
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": ["HTML"],
+    });
+    assert_eq!(nest.render(&page)?, "<p>HTML</p>");
+    Ok(())
+}
+
+/// The error reports the keys & indices traversed to reach the element.
+#[test]
+fn die_on_bad_array_element_with_path() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component": [
+            { "TEMPLATE": "01-simple-component" },
+            {
+                "TEMPLATE": "01-simple-component",
+                "variable": [{ "TEMPLATE": "01-simple-component" }, { "variable": "x" }],
+            },
+        ],
+    });
+
+    let path = "$.simple_component[1].variable[1]";
+    match nest.render(&page) {
+        Err(TemplateNestError::NoNameLabel { path: err_path, .. }) => assert_eq!(err_path, path),
+        _ => panic!("Must return TemplateNestError::NoNameLabel."),
+    }
+    match nest.validate(&page) {
+        Err(errors) => assert!(matches!(
+            &errors[..],
+            [TemplateNestError::NoNameLabel { path: err_path, .. }] if err_path == path
+        )),
+        Ok(_) => panic!("Must return TemplateNestError::NoNameLabel."),
+    }
+    Ok(())
+}
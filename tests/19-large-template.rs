@@ -0,0 +1,57 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_large_template() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+
+    let mut template = String::new();
+    let mut output = String::new();
+    for i in 0..5000 {
+        template.push_str(&format!(
+            "<li id=\"{i}\"><!--% item %--> & <!--% other %--></li>\n"
+        ));
+        output.push_str(&format!("<li id=\"{i}\">Item &amp; & </li>\n"));
+    }
+    fs::write(dir.path().join("large.html"), template)?;
+
+    let nest = TemplateNest::with_directory(dir.path())?;
+    let page = json!({
+        "TEMPLATE": "large",
+        "item": "Item &",
+    });
+    assert_eq!(nest.render(&page)?, output.trim_end());
+    Ok(())
+}
+
+#[test]
+fn render_large_template_with_capacity_hint() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+
+    let mut template = String::new();
+    let mut output = String::new();
+    for i in 0..5000 {
+        template.push_str(&format!("<li id=\"{i}\"><!--% item %--></li>\n"));
+        output.push_str(&format!("<li id=\"{i}\">Item &amp;</li>\n"));
+    }
+    fs::write(dir.path().join("large.html"), template)?;
+
+    let page = json!({
+        "TEMPLATE": "large",
+        "item": "Item &",
+    });
+    for capacity_hint in [0, 64, output.len()] {
+        let nest = TemplateNest::new(
+            TemplateNestOption::builder()
+                .directory(dir.path())
+                .capacity_hint(capacity_hint)
+                .build(),
+        )?;
+        assert_eq!(nest.render(&page)?, output.trim_end());
+    }
+    Ok(())
+}
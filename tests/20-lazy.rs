@@ -0,0 +1,88 @@
+use serde_json::json;
+use std::fs;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// The directory contains a template that is not valid UTF-8, it can only be
+/// initialized if that template is never read.
+fn template_dir() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("simple.html"),
+        "<p><!--% variable %--></p>\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("broken.html"), b"<p>\xff</p>\n").unwrap();
+    dir
+}
+
+#[test]
+fn render_lazy() -> Result<(), TemplateNestError> {
+    let dir = template_dir();
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        lazy: true,
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "simple",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn die_on_eager_index_of_broken_template() -> Result<(), TemplateNestError> {
+    let dir = template_dir();
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        lazy: false,
+        ..Default::default()
+    });
+    assert!(
+        nest.is_err(),
+        "Every template must be read if lazy is false."
+    );
+    Ok(())
+}
+
+#[test]
+fn die_on_lazy_without_directory() {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates-not-found".into(),
+        lazy: true,
+        ..Default::default()
+    });
+    match nest {
+        Err(TemplateNestError::TemplateDirNotFound(_)) => {}
+        _ => panic!("Must return TemplateNestError::TemplateDirNotFound."),
+    }
+}
+
+#[test]
+fn warm_lazy() -> Result<(), TemplateNestError> {
+    let dir = template_dir();
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        lazy: true,
+        ..Default::default()
+    })?;
+    assert_eq!(nest.cache_stats().templates, 0);
+
+    nest.warm(&["simple"])?;
+    let stats = nest.cache_stats();
+    assert_eq!(stats.templates, 1);
+    assert_eq!(stats.variables, 1);
+    assert!(nest.contains_template("simple"));
+
+    assert!(matches!(
+        nest.warm(&["simple", "broken"]),
+        Err(TemplateNestError::TemplateFileReadError { .. })
+    ));
+    assert_eq!(nest.cache_stats().templates, 1);
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn die_on_missing_template() {
+    let nest = TemplateNest::with_directory("templates").unwrap();
+    let page = json!({
+        "TEMPLATE": "01-simple-component-not-found",
+    });
+
+    match nest.render(&page) {
+        Err(TemplateNestError::TemplateFileNotFound(_)) => {}
+        _ => panic!("Must return TemplateNestError::TemplateFileNotFound."),
+    }
+}
+
+#[test]
+fn die_on_missing_nested_template() {
+    let nest = TemplateNest::with_directory("templates").unwrap();
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component": [{
+            "TEMPLATE": "01-simple-component-not-found",
+        }]
+    });
+
+    let err = nest.render(&page).unwrap_err();
+    assert!(err.to_string().contains("referenced by `00-simple-page`"));
+    match err {
+        TemplateNestError::TemplateFileNotFoundIn {
+            missing,
+            referenced_by,
+            variable,
+        } => {
+            assert!(missing.contains("01-simple-component-not-found"));
+            assert_eq!(referenced_by, "00-simple-page");
+            assert_eq!(variable, "simple_component");
+        }
+        _ => panic!("Must return TemplateNestError::TemplateFileNotFoundIn."),
+    }
+}
+
+#[test]
+fn render_missing_template_as_empty() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        missing_template_is_empty: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": [
+            { "TEMPLATE": "01-simple-component-not-found" },
+            "Simple Variable",
+        ],
+    });
+
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+    assert!(nest.validate(&page).is_ok());
+    Ok(())
+}
+
+#[test]
+fn render_missing_template_with_fallback() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        fallback_template: Some("29-missing".to_string()),
+        missing_template_is_empty: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": { "TEMPLATE": "01-simple-component-not-found" },
+    });
+
+    assert_eq!(
+        nest.render(&page)?,
+        r#"<p><div class="missing">01-simple-component-not-found</div></p>"#
+    );
+    assert!(nest.validate(&page).is_ok());
+    Ok(())
+}
+
+/// The fallback template is checked with the MISSING_NAME it's rendered with.
+#[test]
+fn validate_fallback_template_with_bad_params() -> Result<(), TemplateNestError> {
+    let page = json!({ "TEMPLATE": "01-simple-component-not-found" });
+    for (fallback, valid) in [("29-missing", true), ("01-simple-component", false)] {
+        let nest = TemplateNest::new(TemplateNestOption {
+            directory: "templates".into(),
+            fallback_template: Some(fallback.to_string()),
+            die_on_bad_params: true,
+            ..Default::default()
+        })?;
+        assert_eq!(nest.render(&page).is_ok(), valid);
+        assert_eq!(nest.can_render(&page), valid);
+        if !valid {
+            let errors = nest.validate(&page).unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(
+                &errors[0],
+                TemplateNestError::BadParams(param) if param == "MISSING_NAME"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The fallback template must exist.
+#[test]
+fn die_on_missing_fallback_template() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        fallback_template: Some("29-missing-not-found".to_string()),
+        ..Default::default()
+    })?;
+    let page = json!({ "TEMPLATE": "01-simple-component-not-found" });
+
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::TemplateFileNotFound(_))
+    ));
+    assert!(nest.validate(&page).is_err());
+    Ok(())
+}
+
+#[test]
+fn die_on_path_traversal() -> Result<(), TemplateNestError> {
+    for lazy in [false, true] {
+        let nest = TemplateNest::new(TemplateNestOption {
+            directory: "templates/output".into(),
+            lazy,
+            ..Default::default()
+        })?;
+        for name in ["../00-simple-page", "/etc/passwd", "a/../../00-simple-page"] {
+            let page = json!({ "TEMPLATE": name });
+            match nest.render(&page) {
+                Err(TemplateNestError::PathTraversal(path)) => assert_eq!(path, name),
+                _ => panic!("Must return TemplateNestError::PathTraversal."),
+            }
+            assert!(!nest.contains_template(name));
+        }
+    }
+    Ok(())
+}
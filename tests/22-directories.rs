@@ -0,0 +1,56 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_override_directory() -> Result<(), Box<dyn Error>> {
+    let base = tempfile::tempdir()?;
+    fs::write(
+        base.path().join("page.html"),
+        "<main><!--% card %--></main>\n",
+    )?;
+    fs::write(base.path().join("card.html"), "<p>Base Card</p>\n")?;
+    fs::write(base.path().join("footer.html"), "<p>Base Footer</p>\n")?;
+
+    let site = tempfile::tempdir()?;
+    fs::write(site.path().join("card.html"), "<p>Site Card</p>\n")?;
+
+    for lazy in [false, true] {
+        let nest = TemplateNest::new(TemplateNestOption {
+            directory: base.path().into(),
+            directories: vec![site.path().into()],
+            lazy,
+            ..Default::default()
+        })?;
+
+        let page = json!({
+            "TEMPLATE": "page",
+            "card": { "TEMPLATE": "card" },
+        });
+        assert_eq!(nest.render(&page)?, "<main><p>Site Card</p></main>");
+
+        let page = json!({
+            "TEMPLATE": "page",
+            "card": { "TEMPLATE": "footer" },
+        });
+        assert_eq!(nest.render(&page)?, "<main><p>Base Footer</p></main>");
+        assert_eq!(nest.list_templates(), vec!["card", "footer", "page"]);
+    }
+    Ok(())
+}
+
+#[test]
+fn die_on_missing_override_directory() {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        directories: vec!["templates-not-found".into()],
+        ..Default::default()
+    });
+    match nest {
+        Err(TemplateNestError::TemplateDirNotFound(_)) => {}
+        _ => panic!("Must return TemplateNestError::TemplateDirNotFound."),
+    }
+}
@@ -0,0 +1,37 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::TemplateNest;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_after_reload() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("simple.html"),
+        "<p><!--% variable %--></p>\n",
+    )?;
+
+    let mut nest = TemplateNest::with_directory(dir.path())?;
+    assert_eq!(nest.list_templates(), vec!["simple"]);
+
+    fs::write(
+        dir.path().join("added.html"),
+        "<div><!--% variable %--></div>\n",
+    )?;
+    nest.reload()?;
+    assert_eq!(nest.list_templates(), vec!["added", "simple"]);
+
+    let page = json!({
+        "TEMPLATE": "added",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<div>Simple Variable</div>");
+
+    fs::remove_file(dir.path().join("simple.html"))?;
+    nest.reload()?;
+    assert_eq!(nest.list_templates(), vec!["added"]);
+    assert!(!nest.contains_template("simple"));
+    Ok(())
+}
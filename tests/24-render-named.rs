@@ -0,0 +1,68 @@
+use serde_json::json;
+use std::collections::HashMap;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_named_template() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let data = json!({
+        "variable": "Simple Variable",
+    });
+    assert_eq!(
+        nest.render_named("01-simple-component", &data)?,
+        "<p>Simple Variable</p>"
+    );
+    assert_eq!(
+        data,
+        json!({ "variable": "Simple Variable" }),
+        "data must not be modified"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_named_page() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let data = json!({
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+    assert_eq!(
+        nest.render_named("00-simple-page", &data)?,
+        nest.render(&page_output)?
+    );
+    Ok(())
+}
+
+#[test]
+fn die_on_render_named_with_invalid_data() {
+    let nest = TemplateNest::with_directory("templates").unwrap();
+    match nest.render_named("01-simple-component", &json!(["Simple Variable"])) {
+        Err(TemplateNestError::InvalidTemplateHash(_)) => {}
+        _ => panic!("Must return TemplateNestError::InvalidTemplateHash."),
+    }
+}
+
+#[test]
+fn render_map() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let vars = HashMap::from([("variable".to_string(), "Simple & Variable".to_string())]);
+    assert_eq!(
+        nest.render_map("01-simple-component", &vars)?,
+        "<p>Simple &amp; Variable</p>"
+    );
+    assert_eq!(
+        nest.render_map("01-simple-component", &HashMap::new())?,
+        "<p></p>"
+    );
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use serde::Serialize;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[derive(Serialize)]
+struct SimpleComponent {
+    #[serde(rename = "TEMPLATE")]
+    template: String,
+    variable: String,
+}
+
+#[derive(Serialize)]
+struct SimplePage {
+    #[serde(rename = "TEMPLATE")]
+    template: String,
+    variable: String,
+    simple_component: SimpleComponent,
+}
+
+#[test]
+fn render_serializable_struct() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = SimplePage {
+        template: "00-simple-page".to_string(),
+        variable: "Simple Variable".to_string(),
+        simple_component: SimpleComponent {
+            template: "01-simple-component".to_string(),
+            variable: "Simple Variable in Simple Component".to_string(),
+        },
+    };
+    assert_eq!(
+        nest.render_value(&page)?,
+        nest.render_named("output/01-simple-page", &serde_json::Value::Null)?
+    );
+    Ok(())
+}
+
+struct Unserializable;
+
+impl Serialize for Unserializable {
+    fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("unserializable"))
+    }
+}
+
+#[test]
+fn die_on_serialize_error() {
+    let nest = TemplateNest::with_directory("templates").unwrap();
+    match nest.render_value(&Unserializable) {
+        Err(TemplateNestError::Serialize(_)) => {}
+        _ => panic!("Must return TemplateNestError::Serialize."),
+    }
+}
@@ -0,0 +1,193 @@
+use serde_json::json;
+use std::{collections::HashSet, sync::Arc};
+use template_nest::{Escaper, TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn render_with_escaper(escaper: Escaper) -> Result<String, TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        escaper,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "<a href='/'>Tom & \"Jerry\"</a>",
+    });
+    nest.render(&page)
+}
+
+#[test]
+fn render_with_html_escaper() -> Result<(), TemplateNestError> {
+    assert_eq!(
+        render_with_escaper(Escaper::Html)?,
+        "<p>&lt;a href=&#x27;&#x2F;&#x27;&gt;Tom &amp; &quot;Jerry&quot;&lt;&#x2F;a&gt;</p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_xml_escaper() -> Result<(), TemplateNestError> {
+    assert_eq!(
+        render_with_escaper(Escaper::Xml)?,
+        "<p>&lt;a href=&apos;/&apos;&gt;Tom &amp; &quot;Jerry&quot;&lt;/a&gt;</p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_no_escaper() -> Result<(), TemplateNestError> {
+    assert_eq!(
+        render_with_escaper(Escaper::None)?,
+        "<p><a href='/'>Tom & \"Jerry\"</a></p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_custom_escaper() -> Result<(), TemplateNestError> {
+    assert_eq!(
+        render_with_escaper(Escaper::Custom(Arc::new(|text| text.replace('&', "and"))))?,
+        "<p><a href='/'>Tom and \"Jerry\"</a></p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_without_escape_html() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        escape_html: false,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "<b>Simple Variable</b>",
+    });
+    assert_eq!(nest.render(&page)?, "<p><b>Simple Variable</b></p>");
+    Ok(())
+}
+
+#[test]
+fn render_with_raw_prefix() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        raw_prefix: "!".to_string(),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "10-raw-variable",
+        "content": "<b>Content</b>",
+        "title": "<b>Title</b>",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<div><b>Content</b>&lt;b&gt;Title&lt;&#x2F;b&gt;</div>"
+    );
+    Ok(())
+}
+
+/// Raw prefix is part of the variable name unless it's enabled.
+#[test]
+fn render_without_raw_prefix() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "10-raw-variable",
+        "!content": "<b>Content</b>",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<div>&lt;b&gt;Content&lt;&#x2F;b&gt;</div>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_raw_key() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "<b>escaped</b>",
+        "simple_component": { "RAW": "<b>bold</b>" },
+    });
+    let rendered = nest.render(&page)?;
+    assert!(rendered.contains("&lt;b&gt;escaped&lt;&#x2F;b&gt;"));
+    assert!(rendered.contains("<b>bold</b>"));
+    assert!(nest.validate(&page).is_ok());
+    Ok(())
+}
+
+#[test]
+fn render_with_custom_raw_key() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        raw_key: "UNSAFE".to_string(),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": { "UNSAFE": "<b>bold</b>" },
+    });
+    assert_eq!(nest.render(&page)?, "<p><b>bold</b></p>");
+
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": { "RAW": "<b>bold</b>" },
+    });
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::NoNameLabel { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn render_with_javascript_escaper() -> Result<(), TemplateNestError> {
+    assert_eq!(
+        render_with_escaper(Escaper::Javascript)?,
+        r#"<p>\u003Ca href=\'\/\'\u003ETom & \"Jerry\"\u003C\/a\u003E</p>"#
+    );
+    Ok(())
+}
+
+#[test]
+fn javascript_escaper_with_injection() {
+    assert_eq!(
+        Escaper::Javascript.escape("</script><script>alert(1)</script>"),
+        r#"\u003C\/script\u003E\u003Cscript\u003Ealert(1)\u003C\/script\u003E"#
+    );
+    assert_eq!(
+        Escaper::Javascript.escape("\"; alert('x'); \\\"\n\u{2028}"),
+        r#"\"; alert(\'x\'); \\\"\n\u2028"#
+    );
+    assert_eq!(Escaper::Javascript.escape("plain text"), "plain text");
+}
+
+#[test]
+fn render_with_url_escaper() -> Result<(), TemplateNestError> {
+    assert_eq!(
+        render_with_escaper(Escaper::Url)?,
+        "<p>%3Ca%20href%3D%27%2F%27%3ETom%20%26%20%22Jerry%22%3C%2Fa%3E</p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_allow_tags_escaper() -> Result<(), TemplateNestError> {
+    let escaper = Escaper::AllowTags(HashSet::from(["b".to_string(), "a".to_string()]));
+    assert_eq!(
+        render_with_escaper(escaper.clone())?,
+        "<p><a href='/'>Tom &amp; &quot;Jerry&quot;</a></p>"
+    );
+    assert_eq!(
+        escaper.escape("<B>bold</B> <script>alert(1)</script> <i>x</i> <bad> <b"),
+        "<B>bold</B> &lt;script&gt;alert(1)&lt;&#x2F;script&gt; &lt;i&gt;x&lt;&#x2F;i&gt; &lt;bad&gt; &lt;b"
+    );
+    assert_eq!(
+        escaper.escape("</b class='x'>"),
+        "&lt;&#x2F;b class=&#x27;x&#x27;&gt;"
+    );
+    assert_eq!(escaper.escape("plain text"), "plain text");
+    Ok(())
+}
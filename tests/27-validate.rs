@@ -0,0 +1,200 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[test]
+fn validate_page() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    assert!(nest.validate(&page).is_ok());
+    Ok(())
+}
+
+#[test]
+fn validate_page_with_multiple_errors() {
+    let nest = TemplateNest::with_directory("templates").unwrap();
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component": [
+            { "variable": "Simple Variable in Simple Component" },
+            { "TEMPLATE": "01-simple-component-not-found" },
+        ]
+    });
+
+    let errors = nest.validate(&page).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], TemplateNestError::NoNameLabel { .. }));
+    assert!(matches!(
+        errors[1],
+        TemplateNestError::TemplateFileNotFound(_)
+    ));
+}
+
+#[test]
+fn validate_page_with_bad_params() {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_bad_params: true,
+        die_on_unused_variables: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "a_bad_param": "Bad Param",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+        }
+    });
+
+    let errors = nest.validate(&page).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], TemplateNestError::BadParams(_)));
+    assert!(matches!(errors[1], TemplateNestError::MissingVariable(_)));
+}
+
+/// Keys that are not variables of the template are not rendered, a plain
+/// object in them is only an error with die_on_bad_params.
+#[test]
+fn validate_page_with_unused_object() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+        "meta": { "id": 1 },
+    });
+
+    let nest = TemplateNest::with_directory("templates")?;
+    assert!(nest.render(&page).is_ok());
+    assert!(nest.validate(&page).is_ok());
+
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_bad_params: true,
+        ..Default::default()
+    })?;
+    let errors = nest.validate(&page).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], TemplateNestError::BadParams(param) if param == "meta"));
+    Ok(())
+}
+
+#[test]
+fn can_render_page() -> Result<(), TemplateNestError> {
+    let mut nest = TemplateNest::with_directory("templates")?;
+    nest.set_on_missing(|_, _| panic!("can_render must not render"));
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+        }
+    });
+    assert!(nest.can_render(&page));
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"does-not-exist",
+        }
+    });
+    assert!(!nest.can_render(&page));
+    Ok(())
+}
+
+/// can_render is true exactly when render succeeds.
+#[test]
+fn can_render_matches_render() -> Result<(), TemplateNestError> {
+    let pages = [
+        // Blocks.
+        json!({
+            "TEMPLATE": "13-blocks",
+            "variable": "Simple Variable",
+        }),
+        json!({
+            "TEMPLATE": "13-blocks",
+            "BLOCKS": {
+                "title": "Title",
+                "content": { "TEMPLATE": "01-simple-component" },
+            },
+        }),
+        json!({
+            "TEMPLATE": "13-blocks",
+            "BLOCKS": { "content": { "variable": "no label" } },
+        }),
+        json!({
+            "TEMPLATE": "13-blocks",
+            "BLOCKS": { "content": { "TEMPLATE": "does-not-exist" } },
+        }),
+        // Each loops.
+        json!({
+            "TEMPLATE": "23-each-link",
+            "attrs": { "href": "about", "target": "_blank" },
+        }),
+        json!({
+            "TEMPLATE": "23-each-link",
+            "attrs": ["href", "target"],
+        }),
+        // Object keys that are not variables of the template.
+        json!({
+            "TEMPLATE": "01-simple-component",
+            "variable": "Simple Variable",
+            "meta": { "id": 1 },
+        }),
+        json!({
+            "TEMPLATE": "01-simple-component",
+            "variable": "Simple Variable",
+            "meta": { "TEMPLATE": "does-not-exist" },
+        }),
+    ];
+
+    for die_on_bad_params in [false, true] {
+        let nest = TemplateNest::new(TemplateNestOption {
+            directory: "templates".into(),
+            blocks: true,
+            each_loops: true,
+            die_on_bad_params,
+            ..Default::default()
+        })?;
+        for page in &pages {
+            assert_eq!(
+                nest.can_render(page),
+                nest.render(page).is_ok(),
+                "die_on_bad_params: {die_on_bad_params}, page: {page}"
+            );
+        }
+    }
+
+    // The hash returned by pre_render is the one that's rendered.
+    let mut nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_bad_params: true,
+        ..Default::default()
+    })?;
+    nest.set_pre_render(|_, t_hash| {
+        t_hash.insert("added".to_string(), json!("Added"));
+    });
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+    assert!(nest.render(&page).is_err());
+    assert!(!nest.can_render(&page));
+
+    // Templates are not cached.
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        lazy: true,
+        ..Default::default()
+    })?;
+    assert!(nest.can_render(&page));
+    assert_eq!(nest.cache_stats().templates, 0);
+    assert!(nest.render(&page).is_ok());
+    assert_eq!(nest.cache_stats().templates, 1);
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_complex_page_with_deps() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "10-complex-page",
+        "title": "Complex Page",
+        "navigation": {
+            "TEMPLATE": "11-navigation",
+            "banner": {
+                "TEMPLATE": "12-navigation-banner",
+            },
+            "items": [
+                { "TEMPLATE": "13-navigation-item-00-services" },
+                { "TEMPLATE": "13-navigation-item-01-resources" },
+            ]
+        },
+        "main_content": [
+            {
+                "TEMPLATE": "16-vb-brand-cards",
+                "cards": [
+                    { "TEMPLATE": "17-vb-brand-card-00" },
+                    { "TEMPLATE": "17-vb-brand-card-00" },
+                ]
+            }
+        ],
+    });
+
+    let (rendered, deps) = nest.render_with_deps(&page)?;
+    assert_eq!(rendered, nest.render(&page)?);
+    assert_eq!(
+        deps,
+        vec![
+            "10-complex-page",
+            "11-navigation",
+            "12-navigation-banner",
+            "13-navigation-item-00-services",
+            "13-navigation-item-01-resources",
+            "16-vb-brand-cards",
+            "17-vb-brand-card-00",
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_default_template() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        default_template: Some("01-simple-component".to_string()),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+    assert_eq!(nest.render(&page)?, nest.render(&page_output)?);
+    Ok(())
+}
+
+#[test]
+fn die_without_default_template() {
+    let nest = TemplateNest::with_directory("templates").unwrap();
+    let page = json!({
+        "variable": "Simple Variable",
+    });
+    match nest.render(&page) {
+        Err(TemplateNestError::NoNameLabel { .. }) => {}
+        _ => panic!("Must return TemplateNestError::NoNameLabel."),
+    }
+}
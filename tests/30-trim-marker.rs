@@ -0,0 +1,42 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_trim_marker() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        trim_marker: "-".to_string(),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "11-trim-markers",
+        "left": "Left",
+        "right": "Right",
+        "both": "Both",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<p>Left\n</p>\n<p>\n  Right</p>\n<p>Both</p>"
+    );
+    Ok(())
+}
+
+/// Trim markers are part of the variable name unless trim_marker is set.
+#[test]
+fn render_without_trim_marker() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "11-trim-markers",
+        "- left": "Left",
+        "right -": "Right",
+        "- both -": "Both",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<p>\n  Left\n</p>\n<p>\n  Right\n</p>\n<p>\n  Both\n</p>"
+    );
+    Ok(())
+}
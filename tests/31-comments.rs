@@ -0,0 +1,20 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_comment_markers() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        comment_markers: Some(("#".to_string(), "#".to_string())),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "12-comments",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Before Simple Variable After</p>");
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_keep_unresolved() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        keep_unresolved: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+
+    let output = nest.render(&page)?;
+    assert!(output.contains("<p><!--% variable %--></p>"));
+    assert!(output.contains("<p>Simple Variable in Simple Component</p>"));
+    Ok(())
+}
+
+#[test]
+fn render_without_keep_unresolved() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+    });
+    assert_eq!(nest.render(&page)?, "<p></p>");
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use filetime::FileTime;
+use serde_json::json;
+use std::{
+    error::Error,
+    fs,
+    time::{Duration, SystemTime},
+};
+use template_nest::TemplateNest;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Modified templates are re-indexed on render.
+#[test]
+fn render_after_modifying_template() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let template = dir.path().join("simple.html");
+    fs::write(&template, "<p><!--% variable %--></p>\n")?;
+
+    let nest = TemplateNest::with_directory(dir.path())?;
+    let modified = SystemTime::now();
+    for i in 1..=50 {
+        fs::write(&template, format!("<p{i}><!--% variable %--></p{i}>\n"))?;
+        filetime::set_file_mtime(
+            &template,
+            FileTime::from_system_time(modified + Duration::from_secs(i)),
+        )?;
+
+        let page = json!({
+            "TEMPLATE": "simple",
+            "variable": "Simple Variable",
+        });
+        assert_eq!(nest.render(&page)?, format!("<p{i}>Simple Variable</p{i}>"));
+    }
+    Ok(())
+}
+
+/// Invalidated templates are re-indexed on render even if the modified time
+/// didn't change.
+#[test]
+fn render_after_invalidating_template() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let template = dir.path().join("simple.html");
+    fs::write(&template, "<p><!--% variable %--></p>\n")?;
+    let modified = fs::metadata(&template)?.modified()?;
+
+    let nest = TemplateNest::with_directory(dir.path())?;
+    let page = json!({
+        "TEMPLATE": "simple",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+
+    fs::write(&template, "<div><!--% variable %--></div>\n")?;
+    filetime::set_file_mtime(&template, FileTime::from_system_time(modified))?;
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+
+    assert!(nest.invalidate("simple"));
+    assert!(!nest.invalidate("simple"));
+    assert_eq!(nest.render(&page)?, "<div>Simple Variable</div>");
+    Ok(())
+}
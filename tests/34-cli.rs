@@ -0,0 +1,67 @@
+#![cfg(feature = "cli")]
+
+use assert_cmd::Command;
+use std::fs;
+
+const PAGE: &str = r#"{
+    "TEMPLATE": "00-simple-page",
+    "variable": "Simple Variable",
+    "simple_component":  {
+        "TEMPLATE":"01-simple-component",
+        "variable": "Simple Variable in Simple Component"
+    }
+}"#;
+
+fn expected_output() -> String {
+    let output = fs::read_to_string("templates/output/01-simple-page.html").unwrap();
+    format!("{}\n", output.trim_end())
+}
+
+#[test]
+fn render_input_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("page.json");
+    fs::write(&input, PAGE).unwrap();
+
+    Command::cargo_bin("template-nest")
+        .unwrap()
+        .args(["--dir", "templates", "--input"])
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout(expected_output());
+}
+
+#[test]
+fn render_stdin() {
+    Command::cargo_bin("template-nest")
+        .unwrap()
+        .args(["--dir", "templates"])
+        .write_stdin(PAGE)
+        .assert()
+        .success()
+        .stdout(expected_output());
+}
+
+#[test]
+fn die_on_missing_template() {
+    let assert = Command::cargo_bin("template-nest")
+        .unwrap()
+        .args(["--dir", "templates"])
+        .write_stdin(r#"{ "TEMPLATE": "01-simple-component-not-found" }"#)
+        .assert()
+        .failure()
+        .stdout("");
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.starts_with("template-nest: "));
+}
+
+#[test]
+fn die_on_unexpected_argument() {
+    Command::cargo_bin("template-nest")
+        .unwrap()
+        .args(["--unexpected"])
+        .assert()
+        .failure();
+}
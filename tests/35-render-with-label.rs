@@ -0,0 +1,61 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_label() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "type": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "type":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+
+    assert_eq!(
+        nest.render_with_label(&page, "type")?,
+        nest.render(&page_output)?
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_label_ignores_option_label() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+
+    assert!(matches!(
+        nest.render_with_label(&page, "type"),
+        Err(TemplateNestError::NoNameLabel { label, .. }) if label == "type"
+    ));
+    Ok(())
+}
+
+#[test]
+fn render_with_label_and_bad_params() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_bad_params: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "type": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+
+    assert_eq!(
+        nest.render_with_label(&page, "type")?,
+        "<p>Simple Variable</p>"
+    );
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use serde_json::json;
+use std::thread;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_clone() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        die_on_bad_params: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+
+    let clone = nest.clone();
+    let rendered = thread::spawn(move || clone.render(&page)).join().unwrap()?;
+    assert_eq!(rendered, nest.render(&page_output)?);
+    assert_eq!(nest.cache_stats(), nest.clone().cache_stats());
+    Ok(())
+}
+
+#[test]
+fn debug_nest() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let debug = format!("{:?}", nest);
+    assert!(debug.starts_with("TemplateNest {"));
+    assert!(debug.contains("label: \"TEMPLATE\""));
+    Ok(())
+}
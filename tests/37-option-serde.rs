@@ -0,0 +1,50 @@
+#![cfg(feature = "serde")]
+
+use serde_json::json;
+use std::collections::HashMap;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn option_round_trip() {
+    let option = TemplateNestOption {
+        directory: "templates".into(),
+        delimiters: ("{{".to_string(), "}}".to_string()),
+        label: "type".to_string(),
+        show_labels: true,
+        token_escape_char: "\\".to_string(),
+        defaults: HashMap::from([("variable".to_string(), json!("Simple Variable"))]),
+        ..Default::default()
+    };
+
+    let serialized = serde_json::to_string(&option).unwrap();
+    let deserialized: TemplateNestOption = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(format!("{:?}", option), format!("{:?}", deserialized));
+}
+
+#[test]
+fn render_with_deserialized_option() -> Result<(), TemplateNestError> {
+    let option: TemplateNestOption = serde_json::from_value(json!({
+        "directory": "templates",
+        "defaults": { "variable": "Simple Variable" }
+    }))
+    .unwrap();
+    assert_eq!(option.label, "TEMPLATE");
+
+    let nest = TemplateNest::new(option)?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+
+    assert_eq!(nest.render(&page)?, nest.render(&page_output)?);
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn nest() -> Result<TemplateNest, TemplateNestError> {
+    TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        blocks: true,
+        die_on_bad_params: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn render_default_blocks() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "13-blocks",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(
+        nest()?.render(&page)?,
+        "<title>Default Title</title>\n<main><p>Simple Variable</p></main>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_overridden_blocks() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "13-blocks",
+        "BLOCKS": {
+            "title": "Title & More",
+            "content": {
+                "TEMPLATE": "01-simple-component",
+                "variable": "Simple Variable in Simple Component"
+            }
+        }
+    });
+    assert_eq!(
+        nest()?.render(&page)?,
+        "<title>Title &amp; More</title>\n<main><p>Simple Variable in Simple Component</p></main>"
+    );
+    assert!(nest()?.validate(&page).is_ok());
+    assert!(nest()?.can_render(&page));
+
+    // Overrides are validated like the variables.
+    let page = json!({
+        "TEMPLATE": "13-blocks",
+        "BLOCKS": { "content": { "variable": "No Label" } },
+    });
+    let errors = nest()?.validate(&page).unwrap_err();
+    assert!(matches!(
+        &errors[..],
+        [TemplateNestError::NoNameLabel { path, .. }] if path == "$.BLOCKS.content"
+    ));
+    Ok(())
+}
+
+#[test]
+fn render_blocks_disabled() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "13-blocks",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<title>Default Title</title>\n<main><p>Simple Variable</p></main>"
+    );
+    Ok(())
+}
+
+#[test]
+fn die_on_unclosed_block() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("unclosed-block.html"),
+        "<p><!--% block content %-->Unclosed</p>",
+    )?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        blocks: true,
+        ..Default::default()
+    });
+    assert!(matches!(
+        nest,
+        Err(TemplateNestError::UnmatchedBlock(name)) if name == "content"
+    ));
+    Ok(())
+}
+
+/// A block that crosses the end of a conditional is rejected.
+#[test]
+fn die_on_block_overlapping_conditional() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("overlap.html"),
+        "A<!--% if:x %-->B<!--% block a %-->C<!--% endif:x %-->D<!--% endblock %-->E",
+    )?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        blocks: true,
+        conditionals: true,
+        ..Default::default()
+    });
+    assert!(matches!(
+        nest,
+        Err(TemplateNestError::UnmatchedBlock(name)) if name == "a"
+    ));
+    Ok(())
+}
+
+#[test]
+fn render_fragment() -> Result<(), TemplateNestError> {
+    let data = json!({
+        "variable": "Simple Variable",
+    });
+    assert_eq!(
+        nest()?.render_fragment("13-blocks", "content", &data)?,
+        "<p>Simple Variable</p>"
+    );
+    assert_eq!(
+        nest()?.render_fragment("13-blocks", "title", &json!(null))?,
+        "Default Title"
+    );
+    Ok(())
+}
+
+#[test]
+fn die_on_fragment_not_found() -> Result<(), TemplateNestError> {
+    assert!(matches!(
+        nest()?.render_fragment("13-blocks", "footer", &json!({})),
+        Err(TemplateNestError::FragmentNotFound { fragment, .. }) if fragment == "footer"
+    ));
+    Ok(())
+}
+
+/// A conditional that crosses the end of a block is rejected, the template
+/// is indexed lazily so that it's rejected by render_fragment.
+#[test]
+fn die_on_fragment_with_overlapping_conditional() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("overlap.html"),
+        "A<!--% block a %-->B<!--% if:x %-->C<!--% endblock %-->D<!--% endif:x %-->E",
+    )?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        blocks: true,
+        conditionals: true,
+        lazy: true,
+        ..Default::default()
+    })?;
+    assert!(matches!(
+        nest.render_fragment("overlap", "a", &json!({ "x": true })),
+        Err(TemplateNestError::UnmatchedConditional(name)) if name == "x"
+    ));
+    Ok(())
+}
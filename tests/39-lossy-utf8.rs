@@ -0,0 +1,61 @@
+use serde_json::json;
+use std::fs;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn latin1_template() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("latin1.html"),
+        b"<p>Caf\xe9 <!--% variable %--></p>",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn render_invalid_utf8_lossy() -> Result<(), TemplateNestError> {
+    let dir = latin1_template();
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        lossy_utf8: true,
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": "latin1",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Caf\u{fffd} Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn die_on_invalid_utf8() -> Result<(), TemplateNestError> {
+    let dir = latin1_template();
+    let nest = TemplateNest::with_directory(dir.path());
+    assert!(matches!(
+        nest,
+        Err(TemplateNestError::TemplateFileReadError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn read_error_includes_path() -> Result<(), TemplateNestError> {
+    let dir = latin1_template();
+    let err = TemplateNest::with_directory(dir.path()).unwrap_err();
+    let path = dir.path().join("latin1.html").display().to_string();
+    match &err {
+        TemplateNestError::TemplateFileReadError {
+            path: read_path, ..
+        } => {
+            assert_eq!(read_path, &path)
+        }
+        err => panic!("unexpected error: {err}"),
+    }
+    assert!(err.to_string().contains(&path));
+    Ok(())
+}
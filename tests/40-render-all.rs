@@ -0,0 +1,53 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_all() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let pages = [
+        json!({
+            "TEMPLATE": "00-simple-page",
+            "variable": "Simple Variable",
+            "simple_component":  {
+                "TEMPLATE":"01-simple-component",
+                "variable": "Simple Variable in Simple Component"
+            }
+        }),
+        json!({
+            "TEMPLATE": "01-simple-component",
+            "variable": "Simple Variable",
+        }),
+        json!({
+            "TEMPLATE": "03-var-at-begin",
+            "variable": "Simple Variable",
+        }),
+    ];
+
+    let rendered = nest.render_all(&pages)?;
+    let expected = pages
+        .iter()
+        .map(|page| nest.render(page))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(rendered, expected);
+    assert_eq!(rendered[1], "<p>Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn render_all_fails_on_error() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let pages = [
+        json!({ "TEMPLATE": "01-simple-component" }),
+        json!({ "TEMPLATE": "01-simple-component-not-found" }),
+    ];
+
+    assert!(matches!(
+        nest.render_all(&pages),
+        Err(TemplateNestError::TemplateFileNotFound(_))
+    ));
+    assert_eq!(nest.render_all(&[])?, Vec::<String>::new());
+    Ok(())
+}
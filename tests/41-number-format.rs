@@ -0,0 +1,42 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_number_with_format() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .number_format(|number| match number.as_f64() {
+                Some(number) => format!("{:.2}", number),
+                None => number.to_string(),
+            })
+            .build(),
+    )?;
+
+    for (variable, output) in [
+        (json!(1.0), "<p>1.00</p>"),
+        (json!(2.005_1), "<p>2.01</p>"),
+        (json!(42), "<p>42.00</p>"),
+    ] {
+        let page = json!({
+            "TEMPLATE": "01-simple-component",
+            "variable": variable,
+        });
+        assert_eq!(nest.render(&page)?, output);
+    }
+    Ok(())
+}
+
+#[test]
+fn render_number_without_format() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": 1.0,
+    });
+    assert_eq!(nest.render(&page)?, "<p>1.0</p>");
+    Ok(())
+}
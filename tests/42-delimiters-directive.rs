@@ -0,0 +1,38 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_delimiters_directive() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "18-custom-delimiters",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<p>Write <!--% variable %--> to render Simple Variable</p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_default_delimiters_with_directive() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE": "18-custom-delimiters",
+            "variable": "Simple Variable in Custom Delimiters"
+        }
+    });
+    let rendered = nest.render(&page)?;
+    assert!(rendered.contains(
+        "<p>Write <!--% variable %--> to render Simple Variable in Custom Delimiters</p>"
+    ));
+    assert!(!rendered.contains("simple_component"));
+    Ok(())
+}
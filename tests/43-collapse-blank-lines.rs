@@ -0,0 +1,46 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_collapse_blank_lines() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "19-blank-lines",
+        "first": "",
+    });
+
+    let nest = TemplateNest::with_directory("templates")?;
+    assert_eq!(nest.render(&page)?, "<ul>\n\n\n\n\n<li>Item</li>\n</ul>");
+
+    let nest = TemplateNest::new(
+        TemplateNestOption::builder()
+            .directory("templates")
+            .collapse_blank_lines(true)
+            .build(),
+    )?;
+    assert_eq!(nest.render(&page)?, "<ul>\n\n<li>Item</li>\n</ul>");
+    Ok(())
+}
+
+#[test]
+fn render_nested_with_collapse_blank_lines() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        collapse_blank_lines: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "19-blank-lines",
+        "first": {
+            "TEMPLATE": "19-blank-lines",
+            "second": "  ",
+        },
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<ul>\n<ul>\n\n<li>Item</li>\n</ul>\n\n<li>Item</li>\n</ul>"
+    );
+    Ok(())
+}
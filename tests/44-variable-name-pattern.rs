@@ -0,0 +1,47 @@
+use regex::Regex;
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn option(directory: &std::path::Path) -> TemplateNestOption {
+    TemplateNestOption::builder()
+        .directory(directory)
+        .variable_name_pattern(Regex::new("^[a-z_]+$").unwrap())
+        .build()
+}
+
+#[test]
+fn render_with_variable_name_pattern() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("simple.html"),
+        "<p><!--% simple_variable %--></p>",
+    )?;
+
+    let nest = TemplateNest::new(option(dir.path()))?;
+    let page = json!({
+        "TEMPLATE": "simple",
+        "simple_variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn die_on_invalid_variable_name() -> Result<(), Box<dyn Error>> {
+    for token in ["<!--% foo bar %-->", "<!--%  %-->"] {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("invalid.html"), format!("<p>{}</p>", token))?;
+
+        match TemplateNest::new(option(dir.path())) {
+            Err(TemplateNestError::InvalidVariableName(name)) => {
+                assert_eq!(name, token[5..token.len() - 4].trim())
+            }
+            _ => panic!("Must return TemplateNestError::InvalidVariableName"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use serde_json::json;
+use std::collections::HashMap;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn die_on_numeric_name_label() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": 42,
+        "variable": "Simple Variable",
+    });
+
+    match nest.render(&page) {
+        Err(err @ TemplateNestError::InvalidNameLabel { .. }) => {
+            assert_eq!(
+                err.to_string(),
+                "encountered hash with invalid name label type (name label: `TEMPLATE`, value: `42`)"
+            );
+        }
+        _ => panic!("Must return TemplateNestError::InvalidNameLabel"),
+    }
+    Ok(())
+}
+
+#[test]
+fn die_on_array_name_label() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": ["01-simple-component"],
+    });
+
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::InvalidNameLabel { value, .. }) if value == r#"["01-simple-component"]"#
+    ));
+    Ok(())
+}
+
+#[test]
+fn render_with_label_map() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        label_map: Some(HashMap::from([(1, "01-simple-component".to_string())])),
+        ..Default::default()
+    })?;
+
+    let page = json!({
+        "TEMPLATE": 1,
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+
+    let page = json!({
+        "TEMPLATE": 2,
+        "variable": "Simple Variable",
+    });
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::InvalidNameLabel { value, .. }) if value == "2"
+    ));
+    Ok(())
+}
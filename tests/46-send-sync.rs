@@ -0,0 +1,43 @@
+use serde_json::json;
+use std::{sync::Arc, thread};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn nest_is_send_sync() {
+    assert_send_sync::<TemplateNest>();
+    assert_send_sync::<TemplateNestOption>();
+    assert_send_sync::<TemplateNestError>();
+}
+
+#[test]
+fn render_from_multiple_threads() -> Result<(), TemplateNestError> {
+    let nest = Arc::new(TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        lazy: true,
+        ..Default::default()
+    })?);
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let nest = Arc::clone(&nest);
+            thread::spawn(move || {
+                let page = json!({
+                    "TEMPLATE": "01-simple-component",
+                    "variable": format!("Variable {}", i),
+                });
+                nest.render(&page)
+            })
+        })
+        .collect();
+
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.join().unwrap()?, format!("<p>Variable {}</p>", i));
+    }
+    assert_eq!(nest.cache_stats().templates, 1);
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_post_render() -> Result<(), TemplateNestError> {
+    let mut nest = TemplateNest::with_directory("templates")?;
+    nest.set_post_render(|rendered| rendered.to_uppercase());
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+            "variable": "Simple Variable in Simple Component"
+        }
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+
+    assert_eq!(nest.render(&page)?, nest.render(&page_output)?,);
+    assert!(nest
+        .render(&page)?
+        .contains("<P>SIMPLE VARIABLE IN SIMPLE COMPONENT</P>"));
+    Ok(())
+}
+
+/// The hook runs once on the outermost output, not on the nested templates.
+#[test]
+fn post_render_runs_once() -> Result<(), TemplateNestError> {
+    let mut nest = TemplateNest::with_directory("templates")?;
+    nest.set_post_render(|rendered| format!("[{}]", rendered));
+
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": {
+            "TEMPLATE": "01-simple-component",
+            "variable": "Simple Variable",
+        },
+    });
+    assert_eq!(nest.render(&page)?, "[<p><p>Simple Variable</p></p>]");
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use serde_json::{json, Value};
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_pre_render() -> Result<(), TemplateNestError> {
+    let mut nest = TemplateNest::with_directory("templates")?;
+    nest.set_pre_render(|name, t_hash| {
+        if name == "01-simple-component" {
+            t_hash.insert("variable".to_string(), Value::from("Injected Variable"));
+        }
+    });
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component":  {
+            "TEMPLATE":"01-simple-component",
+        }
+    });
+    let rendered = nest.render(&page)?;
+    assert!(rendered.contains("<p>Injected Variable</p>"));
+    assert!(rendered.contains("Simple Variable"));
+
+    assert_eq!(
+        page["simple_component"],
+        json!({ "TEMPLATE": "01-simple-component" }),
+        "page must not be modified"
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+#![cfg(unix)]
+
+use serde_json::json;
+use std::{error::Error, fs, os::unix::fs::symlink};
+use template_nest::{TemplateNest, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Templates directory with a symlink to a shared directory & a symlink back
+/// to itself.
+fn template_dir() -> Result<tempfile::TempDir, Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let templates = dir.path().join("templates");
+    let shared = dir.path().join("shared");
+    fs::create_dir(&templates)?;
+    fs::create_dir(&shared)?;
+    fs::write(shared.join("component.html"), "<p><!--% variable %--></p>")?;
+    symlink(&shared, templates.join("shared"))?;
+    symlink(&templates, templates.join("loop"))?;
+    Ok(dir)
+}
+
+#[test]
+fn follow_symlinks() -> Result<(), Box<dyn Error>> {
+    let dir = template_dir()?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().join("templates"),
+        follow_symlinks: true,
+        ..Default::default()
+    })?;
+
+    assert_eq!(nest.list_templates(), vec!["shared/component"]);
+    let page = json!({
+        "TEMPLATE": "shared/component",
+        "variable": "Simple Variable",
+    });
+    assert_eq!(nest.render(&page)?, "<p>Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn ignore_symlinks() -> Result<(), Box<dyn Error>> {
+    let dir = template_dir()?;
+    let nest = TemplateNest::with_directory(dir.path().join("templates"))?;
+    assert!(nest.list_templates().is_empty());
+    Ok(())
+}
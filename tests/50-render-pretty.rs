@@ -0,0 +1,42 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_pretty() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "20-pretty-page",
+        "content": {
+            "TEMPLATE": "20-pretty-component",
+        },
+    });
+    let page_output = json!({
+        "TEMPLATE": "output/16-pretty-page",
+    });
+
+    assert_eq!(nest.render_pretty(&page)?, nest.render(&page_output)?);
+    Ok(())
+}
+
+/// The line that opens a `<pre>` is only reindented, the whitespace inside
+/// the element is kept.
+#[test]
+fn render_pretty_with_pre() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("page.html"),
+        "<div>\n    <pre>  a  \n  b</pre>\n</div>",
+    )?;
+    let nest = TemplateNest::with_directory(dir.path())?;
+    let page = json!({ "TEMPLATE": "page" });
+
+    assert_eq!(
+        nest.render_pretty(&page)?,
+        "<div>\n  <pre>  a  \n  b</pre>\n</div>"
+    );
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn nest() -> Result<TemplateNest, TemplateNestError> {
+    TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        each_loops: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn render_each_loop() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "23-each-link",
+        "attrs": {
+            "href": "about?a=1&b=2",
+            "target": "_blank",
+        },
+    });
+    assert_eq!(
+        nest()?.render(&page)?,
+        r#"<a href="about?a=1&amp;b=2" target="_blank">link</a>"#
+    );
+    Ok(())
+}
+
+#[test]
+fn render_each_loop_empty() -> Result<(), TemplateNestError> {
+    for attrs in [json!({}), json!(null)] {
+        let page = json!({
+            "TEMPLATE": "23-each-link",
+            "attrs": attrs,
+        });
+        assert_eq!(nest()?.render(&page)?, "<a>link</a>");
+    }
+
+    let page = json!({ "TEMPLATE": "23-each-link" });
+    assert_eq!(nest()?.render(&page)?, "<a>link</a>");
+    Ok(())
+}
+
+#[test]
+fn die_on_each_loop_over_non_object() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "23-each-link",
+        "attrs": ["href", "target"],
+    });
+    assert!(matches!(
+        nest()?.render(&page),
+        Err(TemplateNestError::InvalidEachValue { variable, .. }) if variable == "attrs"
+    ));
+    Ok(())
+}
+
+#[test]
+fn each_loop_disabled() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "23-each-link",
+        "attrs": { "href": "about" },
+    });
+    assert_eq!(nest.render(&page)?, "<a>link</a>");
+    Ok(())
+}
+
+#[test]
+fn render_each_loop_with_label() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "type": "23-each-link",
+        "attrs": { "href": "about" },
+    });
+    assert_eq!(
+        nest()?.render_with_label(&page, "type")?,
+        r#"<a href="about">link</a>"#
+    );
+    Ok(())
+}
+
+#[test]
+fn validate_each_loop() -> Result<(), TemplateNestError> {
+    let nest = nest()?;
+    let page = json!({
+        "TEMPLATE": "23-each-link",
+        "attrs": {
+            "href": "about",
+            "target": "_blank",
+        },
+    });
+    assert!(nest.validate(&page).is_ok());
+    assert!(nest.can_render(&page));
+
+    let page = json!({
+        "TEMPLATE": "23-each-link",
+        "attrs": ["href", "target"],
+    });
+    let errors = nest.validate(&page).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        TemplateNestError::InvalidEachValue { variable, .. } if variable == "attrs"
+    ));
+    assert!(!nest.can_render(&page));
+    Ok(())
+}
+
+/// KEY & VALUE are reserved, the loop template needn't use both.
+#[test]
+fn render_each_loop_with_bad_params() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("list.html"),
+        "<ul><!--% each items with item %--></ul>",
+    )?;
+    fs::write(dir.path().join("item.html"), "<li><!--% VALUE %--></li>")?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        each_loops: true,
+        die_on_bad_params: true,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "list",
+        "items": { "a": "Apple", "b": "Banana" },
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<ul><li>Apple</li><li>Banana</li></ul>"
+    );
+    assert!(nest.can_render(&page));
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_into_buffer() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let component = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component": component.clone(),
+    });
+
+    let mut out = String::new();
+    nest.render_into(&component, &mut out)?;
+    nest.render_into(&page, &mut out)?;
+    assert_eq!(out, nest.render(&component)? + &nest.render(&page)?);
+
+    out.clear();
+    nest.render_into(&component, &mut out)?;
+    assert_eq!(out, "<p>Simple Variable</p>");
+    Ok(())
+}
+
+#[test]
+fn render_into_keeps_buffer_on_error() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let mut out = "<p>Kept</p>".to_string();
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": { "TEMPLATE": "does-not-exist" },
+    });
+    assert!(nest.render_into(&page, &mut out).is_err());
+    assert_eq!(out, "<p>Kept</p>");
+    Ok(())
+}
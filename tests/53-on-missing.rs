@@ -0,0 +1,48 @@
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_with_on_missing() -> Result<(), TemplateNestError> {
+    let mut nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        defaults: HashMap::from([("variable".to_string(), json!("Default Variable"))]),
+        ..Default::default()
+    })?;
+    let missing = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&missing);
+    nest.set_on_missing(move |template, variable| {
+        collected
+            .lock()
+            .unwrap()
+            .push(format!("{template}:{variable}"))
+    });
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "simple_component":  {
+            "TEMPLATE":"00-simple-page",
+            "variable": "Simple Variable",
+            "simple_component": null,
+        }
+    });
+    let output = nest.render(&page)?;
+    assert!(output.contains("<p>Default Variable</p>"));
+    assert!(output.contains("<p>Simple Variable</p>"));
+
+    // Null & the defaults are values so nothing is missing.
+    assert_eq!(*missing.lock().unwrap(), Vec::<String>::new());
+
+    nest.render(&json!({ "TEMPLATE": "00-simple-page" }))?;
+    assert_eq!(
+        *missing.lock().unwrap(),
+        vec!["00-simple-page:simple_component".to_string()]
+    );
+    Ok(())
+}
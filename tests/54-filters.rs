@@ -0,0 +1,110 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn nest() -> Result<TemplateNest, TemplateNestError> {
+    TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        filters: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn render_with_js_filter() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "25-filters",
+        "name": "\"</script><script>alert('x')",
+    });
+    assert_eq!(
+        nest()?.render(&page)?,
+        r#"<script>var name = "\"\u003C\/script\u003E\u003Cscript\u003Ealert(\'x\')";</script>
+<p>&quot;&lt;&#x2F;script&gt;&lt;script&gt;alert(&#x27;x&#x27;)</p>"#
+    );
+    Ok(())
+}
+
+/// Filters are applied in order.
+#[test]
+fn render_with_filter_chain() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("chain.html"), "<!--% name | js | html %-->")?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        filters: true,
+        escape_html: false,
+        ..Default::default()
+    })?;
+    let page = json!({
+        "TEMPLATE": "chain",
+        "name": "<b>",
+    });
+    assert_eq!(nest.render(&page)?, r#"\u003Cb\u003E"#);
+    Ok(())
+}
+
+#[test]
+fn die_on_unknown_filter() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("unknown.html"), "<!--% name | shout %-->")?;
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: dir.path().into(),
+        filters: true,
+        ..Default::default()
+    });
+    assert!(matches!(
+        nest,
+        Err(TemplateNestError::UnknownFilter(filter)) if filter == "shout"
+    ));
+    Ok(())
+}
+
+/// The pipe is part of the variable name unless filters are enabled.
+#[test]
+fn render_without_filters() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = json!({
+        "TEMPLATE": "25-filters",
+        "name | js": "<b>",
+    });
+    assert_eq!(
+        nest.render(&page)?,
+        "<script>var name = \"&lt;b&gt;\";</script>\n<p></p>"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_with_urlencode_filter() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "26-urlencode",
+        "q": "tom & jerry/café~",
+    });
+    assert_eq!(
+        nest()?.render(&page)?,
+        r#"<a href="search?q=tom%20%26%20jerry%2Fcaf%C3%A9~">Search</a>"#
+    );
+    Ok(())
+}
+
+/// Filters are parsed once when indexing, every render uses the same chain.
+#[test]
+fn render_with_filters_repeatedly() -> Result<(), TemplateNestError> {
+    let nest = nest()?;
+    let page = json!({
+        "TEMPLATE": "25-filters",
+        "name": "Tom & \"Jerry\"",
+    });
+    let output = nest.render(&page)?;
+    assert_eq!(
+        output,
+        "<script>var name = \"Tom & \\\"Jerry\\\"\";</script>\n<p>Tom &amp; &quot;Jerry&quot;</p>"
+    );
+    for _ in 0..1000 {
+        assert_eq!(nest.render(&page)?, output);
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+fn nest() -> Result<TemplateNest, TemplateNestError> {
+    TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        classes: true,
+        die_on_bad_params: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn render_merged_classes() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "27-classes",
+        "base": "card card-body",
+        "extra": ["card", "active", "<b>"],
+    });
+    assert_eq!(
+        nest()?.render(&page)?,
+        r#"<div class="card card-body active &lt;b&gt;"></div>"#
+    );
+    Ok(())
+}
+
+#[test]
+fn render_classes_with_missing_source() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "27-classes",
+        "extra": "active",
+    });
+    assert_eq!(nest()?.render(&page)?, r#"<div class="active"></div>"#);
+
+    let page = json!({ "TEMPLATE": "27-classes" });
+    assert_eq!(nest()?.render(&page)?, r#"<div class=""></div>"#);
+    Ok(())
+}
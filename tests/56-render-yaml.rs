@@ -0,0 +1,33 @@
+#![cfg(feature = "yaml")]
+
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_simple_page_from_yaml() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = r#"
+TEMPLATE: 00-simple-page
+variable: Simple Variable
+simple_component:
+  TEMPLATE: 01-simple-component
+  variable: Simple Variable in Simple Component
+"#;
+    let page_output = serde_json::json!({
+        "TEMPLATE": "output/01-simple-page",
+    });
+    assert_eq!(nest.render_yaml(page)?, nest.render(&page_output)?);
+    Ok(())
+}
+
+#[test]
+fn die_on_invalid_yaml() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    assert!(matches!(
+        nest.render_yaml("TEMPLATE: [00-simple-page"),
+        Err(TemplateNestError::InvalidYaml(_))
+    ));
+    Ok(())
+}
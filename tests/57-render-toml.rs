@@ -0,0 +1,44 @@
+#![cfg(feature = "toml")]
+
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_nested_page_from_toml() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let page = r#"
+TEMPLATE = "00-simple-page"
+variable = "Simple Variable"
+
+[[simple_component]]
+TEMPLATE = "01-simple-component"
+variable = "First Component"
+
+[[simple_component]]
+TEMPLATE = "01-simple-component"
+variable = "Second Component"
+"#;
+    let page_json = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "simple_component": [
+            { "TEMPLATE": "01-simple-component", "variable": "First Component" },
+            { "TEMPLATE": "01-simple-component", "variable": "Second Component" },
+        ],
+    });
+    assert_eq!(nest.render_toml(page)?, nest.render(&page_json)?);
+    Ok(())
+}
+
+#[test]
+fn die_on_invalid_toml() -> Result<(), TemplateNestError> {
+    let nest = TemplateNest::with_directory("templates")?;
+    assert!(matches!(
+        nest.render_toml("TEMPLATE = "),
+        Err(TemplateNestError::InvalidToml(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use serde_json::json;
+use template_nest::{TemplateNest, TemplateNestError, TemplateNestOption};
+
+#[test]
+fn die_on_lenient_page_with_strict() -> Result<(), TemplateNestError> {
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+        "a_bad_param": "Bad Param",
+    });
+
+    let nest = TemplateNest::with_directory("templates")?;
+    assert!(nest.render(&page).is_ok());
+
+    let nest = TemplateNest::new(TemplateNestOption {
+        directory: "templates".into(),
+        ..TemplateNestOption::strict()
+    })?;
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::BadParams(param)) if param == "a_bad_param"
+    ));
+
+    let page = json!({
+        "TEMPLATE": "00-simple-page",
+        "variable": "Simple Variable",
+    });
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::MissingVariable(variable)) if variable == "simple_component"
+    ));
+
+    let page = json!({
+        "TEMPLATE": "09-circular-a",
+        "b": {
+            "TEMPLATE": "09-circular-b",
+            "a": {
+                "TEMPLATE": "09-circular-a",
+                "b": "",
+            }
+        }
+    });
+    assert!(matches!(
+        nest.render(&page),
+        Err(TemplateNestError::CircularReference(_))
+    ));
+    Ok(())
+}
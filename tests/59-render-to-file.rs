@@ -0,0 +1,44 @@
+use serde_json::json;
+use std::{error::Error, fs};
+use template_nest::{TemplateNest, TemplateNestError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+#[test]
+fn render_to_file() -> Result<(), Box<dyn Error>> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("index.html");
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+
+    fs::write(&path, "old contents")?;
+    nest.render_to_file(&page, &path)?;
+    assert_eq!(fs::read_to_string(&path)?, nest.render(&page)?);
+
+    // Only the output file is left in the directory.
+    assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn die_on_render_to_missing_directory() -> Result<(), Box<dyn Error>> {
+    let nest = TemplateNest::with_directory("templates")?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("missing").join("index.html");
+    let page = json!({
+        "TEMPLATE": "01-simple-component",
+        "variable": "Simple Variable",
+    });
+
+    match nest.render_to_file(&page, &path) {
+        Err(TemplateNestError::WriteError { path: err_path, .. }) => {
+            assert_eq!(err_path, path.display().to_string())
+        }
+        _ => panic!("Must return TemplateNestError::WriteError."),
+    }
+    Ok(())
+}